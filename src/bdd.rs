@@ -0,0 +1,438 @@
+//! Reduced ordered binary decision diagrams, used to reason exactly on the function of an
+//! equation (model counting, probabilities, enumeration of the models).
+
+use equation::Equation;
+use mccluskey::{PrimeImplicant, Present};
+use num_bigint::BigUint;
+use std::collections::HashMap;
+
+pub const FALSE: usize = 0;
+pub const TRUE: usize = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Op {
+    And,
+    Or,
+    Xor,
+}
+
+/// A decision node, the terminals have the level vars.len().
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Node {
+    level: usize,
+    low: usize,
+    high: usize,
+}
+
+pub struct Bdd {
+    vars: Vec<String>,
+    nodes: Vec<Node>,
+    unique: HashMap<Node, usize>,
+    cache: HashMap<(Op, usize, usize), usize>,
+}
+
+impl Bdd {
+    /// Creates an empty diagram, the variables are tested in the given order.
+    pub fn new(vars: Vec<String>) -> Self {
+        let terminal = Node {
+            level: vars.len(),
+            low: FALSE,
+            high: FALSE,
+        };
+        Bdd {
+            vars,
+            nodes: vec![terminal, terminal],
+            unique: HashMap::new(),
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Builds the diagram of an equation with the variables in the order of get_vars.
+    pub fn from_eq(eq: &Equation) -> (Self, usize) {
+        let mut bdd = Bdd::new(eq.get_owned_vars());
+        let root = bdd.build(eq);
+        (bdd, root)
+    }
+
+    pub fn vars(&self) -> &Vec<String> {
+        &self.vars
+    }
+
+    /// Returns the level of the variable tested by the node.
+    pub fn level(&self, node: usize) -> usize {
+        self.nodes[node].level
+    }
+
+    pub fn low(&self, node: usize) -> usize {
+        self.nodes[node].low
+    }
+
+    pub fn high(&self, node: usize) -> usize {
+        self.nodes[node].high
+    }
+
+    pub fn is_terminal(&self, node: usize) -> bool {
+        node == FALSE || node == TRUE
+    }
+
+    /// Returns the unique node testing the level.
+    fn make(&mut self, level: usize, low: usize, high: usize) -> usize {
+        if low == high {
+            return low;
+        }
+        let node = Node { level, low, high };
+        if let Some(&id) = self.unique.get(&node) {
+            return id;
+        }
+        self.nodes.push(node);
+        self.unique.insert(node, self.nodes.len() - 1);
+        self.nodes.len() - 1
+    }
+
+    /// Returns the node of the variable at the given level.
+    pub fn var(&mut self, level: usize) -> usize {
+        self.make(level, FALSE, TRUE)
+    }
+
+    /// Adds the equation to the diagram and returns its root.
+    pub fn build(&mut self, eq: &Equation) -> usize {
+        match *eq {
+            Equation::Var(ref v) => {
+                let level = self
+                    .vars
+                    .iter()
+                    .position(|x| x == v)
+                    .unwrap_or_else(|| panic!("var not found : {}", v));
+                self.var(level)
+            }
+            Equation::Not(_) => {
+                let inner = self.build(eq.inners()[0]);
+                self.not(inner)
+            }
+            Equation::Sum(_) => eq.inners().into_iter().fold(FALSE, |acc, inner| {
+                let node = self.build(inner);
+                self.or(acc, node)
+            }),
+            Equation::Prod(_) => eq.inners().into_iter().fold(TRUE, |acc, inner| {
+                let node = self.build(inner);
+                self.and(acc, node)
+            }),
+        }
+    }
+
+    pub fn and(&mut self, a: usize, b: usize) -> usize {
+        self.apply(Op::And, a, b)
+    }
+
+    pub fn or(&mut self, a: usize, b: usize) -> usize {
+        self.apply(Op::Or, a, b)
+    }
+
+    pub fn xor(&mut self, a: usize, b: usize) -> usize {
+        self.apply(Op::Xor, a, b)
+    }
+
+    pub fn not(&mut self, a: usize) -> usize {
+        self.xor(a, TRUE)
+    }
+
+    fn apply(&mut self, op: Op, a: usize, b: usize) -> usize {
+        match (op, a, b) {
+            (Op::And, FALSE, _) | (Op::And, _, FALSE) => return FALSE,
+            (Op::And, TRUE, x) | (Op::And, x, TRUE) => return x,
+            (Op::Or, TRUE, _) | (Op::Or, _, TRUE) => return TRUE,
+            (Op::Or, FALSE, x) | (Op::Or, x, FALSE) => return x,
+            (Op::Xor, FALSE, x) | (Op::Xor, x, FALSE) => return x,
+            (Op::Xor, TRUE, TRUE) => return FALSE,
+            (Op::And, x, y) | (Op::Or, x, y) if x == y => return x,
+            (Op::Xor, x, y) if x == y => return FALSE,
+            _ => (),
+        }
+        let key = (op, a.min(b), a.max(b));
+        if let Some(&res) = self.cache.get(&key) {
+            return res;
+        }
+        let level = self.level(a).min(self.level(b));
+        let (a_low, a_high) = self.cofactors(a, level);
+        let (b_low, b_high) = self.cofactors(b, level);
+        let low = self.apply(op, a_low, b_low);
+        let high = self.apply(op, a_high, b_high);
+        let res = self.make(level, low, high);
+        self.cache.insert(key, res);
+        res
+    }
+
+    /// Returns the node with the variable at level set to false then to true.
+    fn cofactors(&self, node: usize, level: usize) -> (usize, usize) {
+        match self.level(node) == level {
+            true => (self.low(node), self.high(node)),
+            false => (node, node),
+        }
+    }
+
+    /// Returns the node with the variable at the given level set to value.
+    pub fn restrict(&mut self, node: usize, level: usize, value: bool) -> usize {
+        self.restrict_cached(node, level, value, &mut HashMap::new())
+    }
+
+    fn restrict_cached(
+        &mut self,
+        node: usize,
+        level: usize,
+        value: bool,
+        cache: &mut HashMap<usize, usize>,
+    ) -> usize {
+        if self.level(node) > level {
+            return node;
+        }
+        if self.level(node) == level {
+            return match value {
+                true => self.high(node),
+                false => self.low(node),
+            };
+        }
+        if let Some(&res) = cache.get(&node) {
+            return res;
+        }
+        let (node_level, low, high) = (self.level(node), self.low(node), self.high(node));
+        let low = self.restrict_cached(low, level, value, cache);
+        let high = self.restrict_cached(high, level, value, cache);
+        let res = self.make(node_level, low, high);
+        cache.insert(node, res);
+        res
+    }
+
+    /// Returns the sorted levels of the variables tested below the node.
+    pub fn support(&self, root: usize) -> Vec<usize> {
+        let mut levels = vec![];
+        let mut seen = vec![false; self.nodes.len()];
+        let mut stack = vec![root];
+        while let Some(node) = stack.pop() {
+            if self.is_terminal(node) || seen[node] {
+                continue;
+            }
+            seen[node] = true;
+            levels.push(self.level(node));
+            stack.push(self.low(node));
+            stack.push(self.high(node));
+        }
+        levels.sort();
+        levels.dedup();
+        levels
+    }
+
+    /// Returns the number of assignments of all the variables for which the node is true.
+    pub fn count(&self, root: usize) -> BigUint {
+        self.count_below(root, &mut HashMap::new()) << self.level(root)
+    }
+
+    /// Number of assignments of the variables from the level of the node.
+    fn count_below(&self, node: usize, memo: &mut HashMap<usize, BigUint>) -> BigUint {
+        match node {
+            FALSE => return BigUint::from(0u32),
+            TRUE => return BigUint::from(1u32),
+            _ => (),
+        }
+        if let Some(res) = memo.get(&node) {
+            return res.clone();
+        }
+        let level = self.level(node);
+        let (low, high) = (self.low(node), self.high(node));
+        let res = (self.count_below(low, memo) << (self.level(low) - level - 1))
+            + (self.count_below(high, memo) << (self.level(high) - level - 1));
+        memo.insert(node, res.clone());
+        res
+    }
+
+    /// Returns the probability of the node being true when the variable at level i is true with
+    /// probability probs[i], independently of the others.
+    pub fn probability(&self, root: usize, probs: &[f64]) -> f64 {
+        self.probability_below(root, probs, &mut HashMap::new())
+    }
+
+    fn probability_below(&self, node: usize, probs: &[f64], memo: &mut HashMap<usize, f64>) -> f64 {
+        match node {
+            FALSE => return 0.0,
+            TRUE => return 1.0,
+            _ => (),
+        }
+        if let Some(&res) = memo.get(&node) {
+            return res;
+        }
+        let p = probs[self.level(node)];
+        let res = (1.0 - p) * self.probability_below(self.low(node), probs, memo)
+            + p * self.probability_below(self.high(node), probs, memo);
+        memo.insert(node, res);
+        res
+    }
+}
+
+/// Lazily enumerates the paths to true of a diagram, which are disjoint cubes covering exactly
+/// the satisfying assignments.
+pub struct Models {
+    bdd: Bdd,
+    stack: Vec<(usize, Vec<Present>)>,
+}
+
+impl Models {
+    pub fn new(bdd: Bdd, root: usize) -> Self {
+        let cube = vec![Present::Any; bdd.vars().len()];
+        Models {
+            bdd,
+            stack: vec![(root, cube)],
+        }
+    }
+
+    /// Returns the variables in the order of the cubes.
+    pub fn vars(&self) -> &Vec<String> {
+        self.bdd.vars()
+    }
+
+    /// Expands the don't care positions of the cubes to get every single assignment.
+    pub fn expanded(self) -> Assignments {
+        Assignments {
+            models: self,
+            current: None,
+        }
+    }
+}
+
+impl Iterator for Models {
+    type Item = PrimeImplicant;
+
+    fn next(&mut self) -> Option<PrimeImplicant> {
+        while let Some((node, cube)) = self.stack.pop() {
+            match node {
+                FALSE => continue,
+                TRUE => return Some(PrimeImplicant::new(cube)),
+                _ => (),
+            }
+            let level = self.bdd.level(node);
+            let mut high_cube = cube.clone();
+            high_cube[level] = Present::Yes;
+            let mut low_cube = cube;
+            low_cube[level] = Present::No;
+            self.stack.push((self.bdd.high(node), high_cube));
+            self.stack.push((self.bdd.low(node), low_cube));
+        }
+        None
+    }
+}
+
+/// Every satisfying assignment, one at a time.
+pub struct Assignments {
+    models: Models,
+    /// The cube being expanded and the values of the variables in the last assignment.
+    current: Option<(Vec<Present>, Vec<bool>)>,
+}
+
+impl Assignments {
+    /// Returns the next assignment of the cube by counting on its don't care positions.
+    fn increment(cube: &[Present], values: &mut [bool]) -> bool {
+        for (pres, value) in cube.iter().zip(values.iter_mut()) {
+            if *pres == Present::Any {
+                *value = !*value;
+                if *value {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+}
+
+impl Iterator for Assignments {
+    type Item = HashMap<String, bool>;
+
+    fn next(&mut self) -> Option<HashMap<String, bool>> {
+        let has_next = match self.current {
+            Some((ref cube, ref mut values)) => Assignments::increment(cube, values),
+            None => false,
+        };
+        if !has_next {
+            let cube = self.models.next()?.list().clone();
+            let values = cube.iter().map(|p| *p == Present::Yes).collect();
+            self.current = Some((cube, values));
+        }
+        let values = &self.current.as_ref().unwrap().1;
+        Some(
+            self.models
+                .vars()
+                .iter()
+                .cloned()
+                .zip(values.iter().cloned())
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests_bdd {
+    use super::*;
+
+    #[test]
+    fn test_canonical() {
+        let vars = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let mut bdd = Bdd::new(vars);
+        let first = bdd.build(&Equation::from("a*b + a*!b + c*!c".to_string()));
+        let second = bdd.build(&Equation::from("a".to_string()));
+        assert_eq!(first, second);
+        let always = bdd.build(&Equation::from("a + !a*b + !b".to_string()));
+        assert_eq!(always, TRUE);
+        let never = bdd.build(&Equation::from("(a + b) * !a * !b".to_string()));
+        assert_eq!(never, FALSE);
+    }
+
+    #[test]
+    fn test_restrict() {
+        let (mut bdd, root) = Bdd::from_eq(&Equation::from("a*b + !a*c".to_string()));
+        let pos = bdd.restrict(root, 0, true);
+        let b = bdd.var(1);
+        assert_eq!(pos, b);
+        let neg = bdd.restrict(root, 0, false);
+        let c = bdd.var(2);
+        assert_eq!(neg, c);
+    }
+
+    #[test]
+    fn test_count() {
+        let (bdd, root) = Bdd::from_eq(&Equation::from("a*b + c".to_string()));
+        assert_eq!(bdd.count(root), BigUint::from(5u32));
+        let (bdd, root) = Bdd::from_eq(&Equation::from("b*c".to_string()));
+        assert_eq!(bdd.count(root), BigUint::from(1u32));
+        let probs = vec![0.5, 0.5];
+        assert_eq!(bdd.probability(root, &probs), 0.25);
+    }
+}
+
+#[cfg(test)]
+mod tests_models {
+    use super::*;
+
+    #[test]
+    fn test_cubes() {
+        let (bdd, root) = Bdd::from_eq(&Equation::from("a*b + !a*c".to_string()));
+        let cubes = Models::new(bdd, root)
+            .map(|p| p.get_string())
+            .collect::<Vec<String>>();
+        assert_eq!(cubes, vec!["0x1", "11x"]);
+    }
+
+    #[test]
+    fn test_expanded() {
+        let eq = Equation::from("a*b + c".to_string());
+        let (bdd, root) = Bdd::from_eq(&eq);
+        let assignments = Models::new(bdd, root).expanded().collect::<Vec<HashMap<String, bool>>>();
+        assert_eq!(assignments.len(), 5);
+        assert!(assignments.iter().all(|a| eq.eval(a)));
+        for i in 0..assignments.len() {
+            assert!(!assignments[i + 1..].contains(&assignments[i]));
+        }
+    }
+
+    #[test]
+    fn test_unsatisfiable() {
+        let (bdd, root) = Bdd::from_eq(&Equation::from("a*!a".to_string()));
+        assert_eq!(Models::new(bdd, root).expanded().count(), 0);
+    }
+}
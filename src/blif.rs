@@ -0,0 +1,299 @@
+//! Reads and writes combinational BLIF netlists (.model/.inputs/.outputs/.names).
+
+use equation::{Equation, Not, Prod, Sum};
+use mccluskey::PrimeImplicant;
+use std::collections::{HashMap, HashSet};
+
+/// A combinational model : the primary inputs and one equation per output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlifModel {
+    pub name: String,
+    pub inputs: Vec<String>,
+    pub outputs: Vec<(String, Equation)>,
+}
+
+/// A .names block : the input signals, the driven signal and the rows of the cover.
+struct Names {
+    inputs: Vec<String>,
+    output: String,
+    rows: Vec<(String, char)>,
+}
+
+impl Names {
+    /// Builds the equation of the cover, replacing every input by its own equation.
+    fn to_eq(&self, inputs: Vec<Equation>) -> Result<Equation, String> {
+        let mut on_set = vec![];
+        let mut off_set = vec![];
+        for &(ref cube, out) in self.rows.iter() {
+            if cube.len() != inputs.len() {
+                return Err(format!("wrong cube width for {} : {}", self.output, cube));
+            }
+            let mut literals = vec![];
+            for (c, input) in cube.chars().zip(inputs.iter()) {
+                match c {
+                    '1' => literals.push(input.clone()),
+                    '0' => literals.push(Equation::Not(Box::new(Not::new(input.clone())))),
+                    '-' => (),
+                    c => return Err(format!("unexpected char in cube : {}", c)),
+                }
+            }
+            let prod = Equation::Prod(Box::new(Prod::new(literals)));
+            match out {
+                '1' => on_set.push(prod),
+                '0' => off_set.push(prod),
+                c => return Err(format!("unexpected output value : {}", c)),
+            }
+        }
+        match (on_set.is_empty(), off_set.is_empty()) {
+            (_, true) => Ok(Equation::Sum(Box::new(Sum::new(on_set)))),
+            (true, false) => Ok(Equation::Not(Box::new(Not::new(Equation::Sum(Box::new(
+                Sum::new(off_set),
+            )))))),
+            _ => Err(format!("mixed on-set and off-set for {}", self.output)),
+        }
+    }
+}
+
+/// Joins the lines ending with a backslash and removes the comments.
+fn logical_lines(text: &str) -> Vec<String> {
+    let mut lines = vec![];
+    let mut current = String::new();
+    for line in text.lines() {
+        let line = line.split('#').next().unwrap().trim_end();
+        if let Some(joined) = line.strip_suffix('\\') {
+            current.push_str(joined);
+            current.push(' ');
+        } else {
+            current.push_str(line);
+            if !current.trim().is_empty() {
+                lines.push(current.trim().to_string());
+            }
+            current = String::new();
+        }
+    }
+    if !current.trim().is_empty() {
+        lines.push(current.trim().to_string());
+    }
+    lines
+}
+
+/// Parses a BLIF model and returns the equation of every output in terms of the inputs.
+pub fn read_blif(text: &str) -> Result<BlifModel, String> {
+    let mut name = String::new();
+    let mut inputs = vec![];
+    let mut outputs = vec![];
+    let mut blocks: Vec<Names> = vec![];
+    for line in logical_lines(text) {
+        let mut words = line.split_whitespace().map(|w| w.to_string());
+        let first = words.next().unwrap();
+        match first.as_ref() {
+            ".model" => name = words.collect::<Vec<String>>().join(" "),
+            ".inputs" => inputs.extend(words),
+            ".outputs" => outputs.extend(words),
+            ".names" => {
+                let mut signals = words.collect::<Vec<String>>();
+                let output = signals
+                    .pop()
+                    .ok_or_else(|| ".names without any signal".to_string())?;
+                blocks.push(Names {
+                    inputs: signals,
+                    output,
+                    rows: vec![],
+                });
+            }
+            ".end" => break,
+            c if c.starts_with('.') => return Err(format!("unsupported construct : {}", c)),
+            _ => {
+                let block = blocks
+                    .last_mut()
+                    .ok_or_else(|| format!("cover row outside of .names : {}", line))?;
+                let row = line.split_whitespace().collect::<Vec<&str>>();
+                let (cube, out) = match row.len() {
+                    1 => ("", row[0]),
+                    2 => (row[0], row[1]),
+                    _ => return Err(format!("malformed cover row : {}", line)),
+                };
+                if out.len() != 1 {
+                    return Err(format!("malformed cover row : {}", line));
+                }
+                block.rows.push((cube.to_string(), out.chars().next().unwrap()));
+            }
+        }
+    }
+    let mut drivers = HashMap::new();
+    for block in blocks.iter() {
+        if drivers.insert(block.output.clone(), block).is_some() {
+            return Err(format!("signal driven twice : {}", block.output));
+        }
+    }
+    let mut resolved = HashMap::new();
+    let mut equations = vec![];
+    for out in outputs.iter() {
+        let eq = resolve(out, &inputs, &drivers, &mut resolved, &mut HashSet::new())?;
+        equations.push((out.clone(), eq));
+    }
+    Ok(BlifModel {
+        name,
+        inputs,
+        outputs: equations,
+    })
+}
+
+/// Returns the equation of a signal, recursively inlining the .names driving its inputs.
+fn resolve(
+    signal: &String,
+    inputs: &Vec<String>,
+    drivers: &HashMap<String, &Names>,
+    resolved: &mut HashMap<String, Equation>,
+    visiting: &mut HashSet<String>,
+) -> Result<Equation, String> {
+    if let Some(eq) = resolved.get(signal) {
+        return Ok(eq.clone());
+    }
+    if inputs.contains(signal) {
+        return Ok(Equation::Var(signal.clone()));
+    }
+    let block = drivers
+        .get(signal)
+        .ok_or_else(|| format!("undriven signal : {}", signal))?;
+    if !visiting.insert(signal.clone()) {
+        return Err(format!("combinational loop through : {}", signal));
+    }
+    let mut fanin = vec![];
+    for input in block.inputs.iter() {
+        fanin.push(resolve(input, inputs, drivers, resolved, visiting)?);
+    }
+    let eq = block.to_eq(fanin)?;
+    visiting.remove(signal);
+    resolved.insert(signal.clone(), eq.clone());
+    Ok(eq)
+}
+
+/// Returns the rows of the cover of a simplified equation, in the order of vars.
+fn cover(eq: &Equation, vars: &Vec<&String>) -> Vec<String> {
+    let terms = match eq {
+        &Equation::Sum(_) => eq.inners(),
+        _ => vec![eq],
+    };
+    let mut rows = vec![];
+    for term in terms {
+        if let Equation::Prod(ref p) = *term {
+            if p.is_contradiction() {
+                continue;
+            }
+        }
+        let row = PrimeImplicant::from_eq(term, vars).get_string().replace("x", "-");
+        if !rows.contains(&row) {
+            rows.push(row);
+        }
+    }
+    rows
+}
+
+/// Simplifies every output and writes the model with one .names block per output.
+pub fn write_blif(model: &BlifModel) -> String {
+    let mut text = format!(".model {}\n", model.name);
+    text.push_str(&format!(".inputs {}\n", model.inputs.join(" ")));
+    text.push_str(&format!(
+        ".outputs {}\n",
+        model
+            .outputs
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<String>>()
+            .join(" ")
+    ));
+    for (name, eq) in model.outputs.iter() {
+        let simplified = eq.clone().complete_simplify();
+        let vars = simplified.get_vars();
+        let mut signals = vars.iter().map(|&v| v.clone()).collect::<Vec<String>>();
+        signals.push(name.clone());
+        text.push_str(&format!(".names {}\n", signals.join(" ")));
+        for row in cover(&simplified, &vars) {
+            match row.is_empty() {
+                true => text.push_str("1\n"),
+                false => text.push_str(&format!("{} 1\n", row)),
+            }
+        }
+    }
+    text.push_str(".end\n");
+    text
+}
+
+#[cfg(test)]
+mod tests_read_blif {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_single_names() {
+        let model = read_blif(
+            ".model test\n.inputs a b c\n.outputs y\n.names a b c y\n11- 1\n--0 1\n.end\n",
+        ).unwrap();
+        assert_eq!(model.name, "test");
+        assert_eq!(model.inputs, vec!["a", "b", "c"]);
+        model.outputs[0]
+            .1
+            .compare_random_values(&Equation::from("a*b + !c".to_string()), 100);
+    }
+
+    #[test]
+    fn test_multi_level() {
+        let model = read_blif(
+            "# a comment\n.model ml\n.inputs a b \\\n c\n.outputs y z\n\
+             .names a b t\n11 1\n.names t c y\n1- 1\n-1 1\n.names t z\n1 0\n.end\n",
+        ).unwrap();
+        model.outputs[0]
+            .1
+            .compare_random_values(&Equation::from("a*b + c".to_string()), 100);
+        model.outputs[1]
+            .1
+            .compare_random_values(&Equation::from("!(a*b)".to_string()), 100);
+    }
+
+    #[test]
+    fn test_constants() {
+        let model = read_blif(".model k\n.inputs a\n.outputs one zero\n.names one\n1\n.names zero\n.end\n")
+            .unwrap();
+        let vars = HashMap::new();
+        assert!(model.outputs[0].1.eval(&vars));
+        assert!(!model.outputs[1].1.eval(&vars));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(read_blif(".model e\n.inputs a\n.outputs y\n.names b y\n1 1\n").is_err());
+        assert!(read_blif(".model e\n.inputs a\n.outputs y\n.names a t y\n11 1\n.names y t\n1 1\n").is_err());
+        assert!(read_blif(".model e\n.inputs a\n.outputs y\n.latch a y\n").is_err());
+    }
+}
+
+#[cfg(test)]
+mod tests_write_blif {
+    use super::*;
+
+    #[test]
+    fn test_write() {
+        let model = BlifModel {
+            name: "w".to_string(),
+            inputs: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+            outputs: vec![(
+                "y".to_string(),
+                Equation::from("a*b*c + a*b*!c + !a*c".to_string()),
+            )],
+        };
+        assert_eq!(
+            write_blif(&model),
+            ".model w\n.inputs a b c\n.outputs y\n.names a b c y\n0-1 1\n11- 1\n.end\n"
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let text = ".model r\n.inputs a b c d\n.outputs y\n.names a b t\n10 1\n01 1\n\
+                    .names t c d y\n1-- 1\n-11 1\n.end\n";
+        let model = read_blif(text).unwrap();
+        let again = read_blif(&write_blif(&model)).unwrap();
+        again.outputs[0].1.compare_random_values(&model.outputs[0].1, 1000);
+    }
+}
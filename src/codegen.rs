@@ -0,0 +1,216 @@
+//! Generates C and Rust functions evaluating an equation.
+//!
+//! The parameters follow the order of Equation::get_vars, in the packed variants the variable
+//! number i is the bit i of the input.
+
+use equation::Equation;
+use std::collections::{HashMap, HashSet};
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "break", "const", "continue", "crate", "else", "enum", "extern", "false", "fn", "for",
+    "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return",
+    "self", "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where",
+    "while", "async", "await", "dyn",
+];
+
+const C_KEYWORDS: &[&str] = &[
+    "auto", "break", "case", "char", "const", "continue", "default", "do", "double", "else",
+    "enum", "extern", "float", "for", "goto", "if", "int", "long", "register", "return", "short",
+    "signed", "sizeof", "static", "struct", "switch", "typedef", "union", "unsigned", "void",
+    "volatile", "while", "bool", "true", "false", "in",
+];
+
+/// Turns a variable name into a valid identifier (eg. bus[3] => bus_3_).
+fn identifier(name: &str, keywords: &[&str]) -> String {
+    let mut id = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+    if id.chars().next().is_none_or(|c| c.is_ascii_digit()) {
+        id.insert(0, '_');
+    }
+    if keywords.contains(&id.as_ref()) {
+        id.push('_');
+    }
+    id
+}
+
+/// Returns the identifier of every variable, suffixing the ones which would collide (eg. a[1] and
+/// a_1_), the names already valid keeping their identifier.
+fn identifiers(eq: &Equation, keywords: &[&str]) -> HashMap<String, String> {
+    let vars = eq.get_owned_vars();
+    let (valid, renamed): (Vec<String>, Vec<String>) =
+        vars.into_iter().partition(|v| identifier(v, keywords) == *v);
+    let mut taken = valid.iter().cloned().collect::<HashSet<String>>();
+    let mut ids = valid.into_iter().map(|v| (v.clone(), v)).collect::<HashMap<String, String>>();
+    for v in renamed {
+        let id = identifier(&v, keywords);
+        let mut unique = id.clone();
+        let mut i = 2;
+        while taken.contains(&unique) {
+            unique = format!("{}_{}", id, i);
+            i += 1;
+        }
+        taken.insert(unique.clone());
+        ids.insert(v, unique);
+    }
+    ids
+}
+
+/// Returns the expression using the C and Rust operators, which are the same.
+fn expr(eq: &Equation, var: &dyn Fn(&String) -> String, top: bool) -> String {
+    let (op, empty) = match *eq {
+        Equation::Var(ref v) => return var(v),
+        Equation::Not(_) => return format!("!{}", expr(eq.inners()[0], var, false)),
+        Equation::Sum(_) => (" || ", "false"),
+        Equation::Prod(_) => (" && ", "true"),
+    };
+    let inners = eq.inners();
+    match inners.len() {
+        0 => empty.to_string(),
+        1 => expr(inners[0], var, top),
+        _ => {
+            let joined = inners
+                .into_iter()
+                .map(|inner| expr(inner, var, false))
+                .collect::<Vec<String>>()
+                .join(op);
+            match top {
+                true => joined,
+                false => format!("({})", joined),
+            }
+        }
+    }
+}
+
+/// Returns the position of every variable, failing if they do not fit in an u64.
+fn bit_positions(eq: &Equation) -> Result<Vec<String>, String> {
+    let vars = eq.get_owned_vars();
+    match vars.len() {
+        0..=64 => Ok(vars),
+        n => Err(format!("too many variables to pack in an u64 : {}", n)),
+    }
+}
+
+/// Returns a C function taking one bool per variable.
+pub fn to_c(eq: &Equation, name: &str) -> String {
+    let ids = identifiers(eq, C_KEYWORDS);
+    let params = eq
+        .get_vars()
+        .iter()
+        .map(|v| format!("bool {}", ids[*v]))
+        .collect::<Vec<String>>();
+    format!(
+        "#include <stdbool.h>\n\nbool {}({}) {{\n    return {};\n}}\n",
+        identifier(name, C_KEYWORDS),
+        match params.is_empty() {
+            true => "void".to_string(),
+            false => params.join(", "),
+        },
+        expr(eq, &|v| ids[v].clone(), true)
+    )
+}
+
+/// Returns a C function taking the variables packed in an uint64_t.
+pub fn to_c_packed(eq: &Equation, name: &str) -> Result<String, String> {
+    let vars = bit_positions(eq)?;
+    Ok(format!(
+        "#include <stdbool.h>\n#include <stdint.h>\n\nbool {}(uint64_t input) {{\n    return {};\n}}\n",
+        identifier(name, C_KEYWORDS),
+        expr(
+            eq,
+            &|v| format!("((input >> {}) & 1)", vars.iter().position(|x| x == v).unwrap()),
+            true
+        )
+    ))
+}
+
+/// Returns a Rust function taking one bool per variable.
+pub fn to_rust(eq: &Equation, name: &str) -> String {
+    let ids = identifiers(eq, RUST_KEYWORDS);
+    let params = eq
+        .get_vars()
+        .iter()
+        .map(|v| format!("{}: bool", ids[*v]))
+        .collect::<Vec<String>>();
+    format!(
+        "pub fn {}({}) -> bool {{\n    {}\n}}\n",
+        identifier(name, RUST_KEYWORDS),
+        params.join(", "),
+        expr(eq, &|v| ids[v].clone(), true)
+    )
+}
+
+/// Returns a Rust function taking the variables packed in an u64.
+pub fn to_rust_packed(eq: &Equation, name: &str) -> Result<String, String> {
+    let vars = bit_positions(eq)?;
+    Ok(format!(
+        "pub fn {}(input: u64) -> bool {{\n    {}\n}}\n",
+        identifier(name, RUST_KEYWORDS),
+        expr(
+            eq,
+            &|v| format!("((input >> {}) & 1 == 1)", vars.iter().position(|x| x == v).unwrap()),
+            true
+        )
+    ))
+}
+
+#[cfg(test)]
+mod tests_codegen {
+    use super::*;
+
+    #[test]
+    fn test_c() {
+        let eq = Equation::from("a * !b + !(c + int)".to_string());
+        assert_eq!(
+            to_c(&eq, "f"),
+            "#include <stdbool.h>\n\nbool f(bool a, bool b, bool c, bool int_) {\n    \
+             return (a && !b) || !(c || int_);\n}\n"
+        );
+        assert_eq!(
+            to_c_packed(&eq, "f").unwrap(),
+            "#include <stdbool.h>\n#include <stdint.h>\n\nbool f(uint64_t input) {\n    \
+             return (((input >> 0) & 1) && !((input >> 1) & 1)) || \
+             !(((input >> 2) & 1) || ((input >> 3) & 1));\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_rust() {
+        let eq = Equation::from_verilog("a[0] & ~fn | b".to_string()).unwrap();
+        assert_eq!(
+            to_rust(&eq, "check"),
+            "pub fn check(a_0_: bool, b: bool, fn_: bool) -> bool {\n    (a_0_ && !fn_) || b\n}\n"
+        );
+        assert_eq!(
+            to_rust_packed(&eq, "check").unwrap(),
+            "pub fn check(input: u64) -> bool {\n    \
+             (((input >> 0) & 1 == 1) && !((input >> 2) & 1 == 1)) || ((input >> 1) & 1 == 1)\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_constant() {
+        let eq = Equation::from("a + !a".to_string()).complete_simplify();
+        assert_eq!(to_c(&eq, "t"), "#include <stdbool.h>\n\nbool t(void) {\n    return true;\n}\n");
+        assert_eq!(to_rust(&eq, "t"), "pub fn t() -> bool {\n    true\n}\n");
+    }
+
+    #[test]
+    fn test_collisions() {
+        let eq = Equation::from("a[1] * a_1_ + a_1__2".to_string());
+        assert_eq!(
+            to_rust(&eq, "f"),
+            "pub fn f(a_1__3: bool, a_1_: bool, a_1__2: bool) -> bool {\n    (a_1__3 && a_1_) || a_1__2\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_too_many_variables() {
+        let names = (0..65).map(|i| format!("x{}", i)).collect::<Vec<String>>();
+        let eq = Equation::from(names.join(" + "));
+        assert!(to_c_packed(&eq, "f").is_err());
+        assert!(to_rust_packed(&eq, "f").is_err());
+        assert!(to_rust(&eq, "f").contains("x64: bool"));
+    }
+}
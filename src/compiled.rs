@@ -0,0 +1,216 @@
+//! Compiles an equation to a flat list of instructions evaluated on 64 assignments at once.
+
+use equation::Equation;
+use std::collections::HashMap;
+
+/// Every instruction writes its result in the register of the same index.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instr {
+    Load(usize),
+    Const(bool),
+    Not(usize),
+    And(usize, usize),
+    Or(usize, usize),
+}
+
+/// Patterns of the 6 first variables when enumerating 64 consecutive assignments.
+const PATTERNS: [u64; 6] = [
+    0xAAAA_AAAA_AAAA_AAAA,
+    0xCCCC_CCCC_CCCC_CCCC,
+    0xF0F0_F0F0_F0F0_F0F0,
+    0xFF00_FF00_FF00_FF00,
+    0xFFFF_0000_FFFF_0000,
+    0xFFFF_FFFF_0000_0000,
+];
+
+/// Maximum number of variables of a truth table, which has 2^(n - 6) words.
+pub const MAX_TABLE_VARS: usize = 26;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Compiled {
+    vars: Vec<String>,
+    instrs: Vec<Instr>,
+}
+
+impl Compiled {
+    /// Compiles the equation with the variables in the order of get_vars.
+    pub fn new(eq: &Equation) -> Self {
+        Compiled::with_vars(eq, &eq.get_owned_vars())
+    }
+
+    /// Compiles the equation with the given order of the variables, they must contain those of
+    /// the equation.
+    pub fn with_vars(eq: &Equation, vars: &[String]) -> Self {
+        let positions = vars
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (v.clone(), i))
+            .collect::<HashMap<String, usize>>();
+        let mut compiled = Compiled {
+            vars: vars.to_vec(),
+            instrs: vec![],
+        };
+        compiled.compile(eq, &positions);
+        compiled
+    }
+
+    pub fn vars(&self) -> &Vec<String> {
+        &self.vars
+    }
+
+    pub fn instrs(&self) -> &Vec<Instr> {
+        &self.instrs
+    }
+
+    fn push(&mut self, instr: Instr) -> usize {
+        self.instrs.push(instr);
+        self.instrs.len() - 1
+    }
+
+    /// Adds the instructions of the equation and returns the register holding its value.
+    fn compile(&mut self, eq: &Equation, positions: &HashMap<String, usize>) -> usize {
+        match *eq {
+            Equation::Var(ref v) => {
+                let pos = *positions
+                    .get(v)
+                    .unwrap_or_else(|| panic!("var not found : {}", v));
+                self.push(Instr::Load(pos))
+            }
+            Equation::Not(_) => {
+                let inner = self.compile(eq.inners()[0], positions);
+                self.push(Instr::Not(inner))
+            }
+            Equation::Sum(_) | Equation::Prod(_) => {
+                let is_sum = matches!(*eq, Equation::Sum(_));
+                let mut inners = eq.inners().into_iter();
+                let mut acc = match inners.next() {
+                    Some(first) => self.compile(first, positions),
+                    None => return self.push(Instr::Const(!is_sum)),
+                };
+                for inner in inners {
+                    let reg = self.compile(inner, positions);
+                    acc = match is_sum {
+                        true => self.push(Instr::Or(acc, reg)),
+                        false => self.push(Instr::And(acc, reg)),
+                    };
+                }
+                acc
+            }
+        }
+    }
+
+    /// Evaluates 64 assignments at once, inputs[i] holding the 64 values of the variable i.
+    pub fn eval_words(&self, inputs: &[u64], regs: &mut Vec<u64>) -> u64 {
+        regs.clear();
+        for instr in self.instrs.iter() {
+            let value = match *instr {
+                Instr::Load(i) => inputs[i],
+                Instr::Const(true) => !0,
+                Instr::Const(false) => 0,
+                Instr::Not(a) => !regs[a],
+                Instr::And(a, b) => regs[a] & regs[b],
+                Instr::Or(a, b) => regs[a] | regs[b],
+            };
+            regs.push(value);
+        }
+        *regs.last().unwrap()
+    }
+
+    /// Evaluates a single assignment, values[i] being the value of the variable i.
+    pub fn eval(&self, values: &[bool]) -> bool {
+        let inputs = values
+            .iter()
+            .map(|&v| if v { !0 } else { 0 })
+            .collect::<Vec<u64>>();
+        self.eval_words(&inputs, &mut vec![]) & 1 == 1
+    }
+
+    /// Returns the values of the 64 assignments starting at 64 * word, the variable i taking the
+    /// bit i of the number of the assignment.
+    pub fn enumerate_word(&self, word: u64, regs: &mut Vec<u64>) -> u64 {
+        let inputs = (0..self.vars.len())
+            .map(|i| match i {
+                0..=5 => PATTERNS[i],
+                _ if (word >> (i - 6)) & 1 == 1 => !0,
+                _ => 0,
+            })
+            .collect::<Vec<u64>>();
+        self.eval_words(&inputs, regs)
+    }
+
+    /// Returns the truth table packed in words, the bit k of the word w being the value of the
+    /// assignment number 64 * w + k, failing above MAX_TABLE_VARS variables.
+    pub fn truth_table(&self) -> Result<Vec<u64>, String> {
+        let nb_vars = self.vars.len();
+        let mut regs = Vec::with_capacity(self.instrs.len());
+        if nb_vars > MAX_TABLE_VARS {
+            return Err(format!(
+                "truth tables are limited to {} variables, found {}",
+                MAX_TABLE_VARS, nb_vars
+            ));
+        }
+        if nb_vars < 6 {
+            let mask = (1u64 << (1 << nb_vars)) - 1;
+            return Ok(vec![self.enumerate_word(0, &mut regs) & mask]);
+        }
+        Ok((0..1u64 << (nb_vars - 6))
+            .map(|w| self.enumerate_word(w, &mut regs))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests_compiled {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_instrs() {
+        let compiled = Compiled::new(&Equation::from("a * !b + c".to_string()).simplified());
+        assert_eq!(
+            compiled.instrs(),
+            &vec![
+                Instr::Load(0),
+                Instr::Load(1),
+                Instr::Not(1),
+                Instr::And(0, 2),
+                Instr::Load(2),
+                Instr::Or(3, 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_same_as_eval() {
+        let eq = Equation::from("I & !B | (A + B) and (c + a./y) + !(x * z)".to_string());
+        let compiled = Compiled::new(&eq);
+        let table = compiled.truth_table().unwrap();
+        assert_eq!(table.len(), 4);
+        for assignment in 0..256 {
+            let values = (0..8).map(|i| (assignment >> i) & 1 == 1).collect::<Vec<bool>>();
+            let vars = compiled
+                .vars()
+                .iter()
+                .cloned()
+                .zip(values.iter().cloned())
+                .collect::<HashMap<String, bool>>();
+            assert_eq!(compiled.eval(&values), eq.eval(&vars));
+            assert_eq!((table[assignment / 64] >> (assignment % 64)) & 1 == 1, eq.eval(&vars));
+        }
+    }
+
+    #[test]
+    fn test_small_table() {
+        assert_eq!(Compiled::new(&Equation::from("a * b".to_string())).truth_table(), Ok(vec![0b1000]));
+        assert_eq!(Compiled::new(&Equation::from("a + !b".to_string())).truth_table(), Ok(vec![0b1011]));
+        let always = Equation::from("a + !a".to_string()).complete_simplify();
+        assert_eq!(Compiled::new(&always).truth_table(), Ok(vec![1]));
+    }
+
+    #[test]
+    fn test_too_many_variables() {
+        let names = (0..70).map(|i| format!("x{}", i)).collect::<Vec<String>>();
+        let eq = Equation::from(names.join(" * "));
+        assert!(Compiled::new(&eq).truth_table().is_err());
+    }
+}
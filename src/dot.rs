@@ -0,0 +1,162 @@
+//! Graphviz output of the structure of equations.
+
+use equation::Equation;
+use std::collections::HashMap;
+
+/// Options of the generated graph.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DotOptions {
+    pub graph_name: String,
+    /// Adds the depth of every node to its label.
+    pub depth: bool,
+    /// Adds the number of nodes below every node, itself included, to its label.
+    pub size: bool,
+}
+
+impl DotOptions {
+    pub fn new(graph_name: &str) -> Self {
+        DotOptions {
+            graph_name: graph_name.to_string(),
+            depth: false,
+            size: false,
+        }
+    }
+
+    /// Returns the attributes of the node of the equation, the operators being named as gates
+    /// in the circuit.
+    fn node(&self, eq: &Equation, gate: bool) -> String {
+        let (mut label, shape) = match *eq {
+            _ if eq.as_constant().is_some() => (constant_label(eq), "box"),
+            Equation::Var(ref v) => (escape(v), "box"),
+            Equation::Not(_) if gate => ("NOT".to_string(), "invtriangle"),
+            Equation::Prod(_) if gate => ("AND".to_string(), "invhouse"),
+            Equation::Sum(_) if gate => ("OR".to_string(), "invhouse"),
+            Equation::Not(_) => ("!".to_string(), "circle"),
+            Equation::Prod(_) => ("*".to_string(), "circle"),
+            Equation::Sum(_) => ("+".to_string(), "circle"),
+        };
+        if self.depth {
+            label.push_str(&format!("\\ndepth {}", eq.depth(0)));
+        }
+        if self.size {
+            label.push_str(&format!("\\nsize {}", size(eq)));
+        }
+        format!("[label=\"{}\", shape={}]", label, shape)
+    }
+}
+
+/// Escapes the backslashes and the quotes of a text written between quotes.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn constant_label(eq: &Equation) -> String {
+    match eq.as_constant() {
+        Some(true) => "1".to_string(),
+        _ => "0".to_string(),
+    }
+}
+
+/// Returns the number of nodes of the tree.
+fn size(eq: &Equation) -> usize {
+    let mut nodes = 0;
+    eq.walk(&mut |_| nodes += 1);
+    nodes
+}
+
+/// Returns the graph of the tree, every node of the equation being drawn once per occurrence.
+pub fn to_dot_tree(eq: &Equation, options: &DotOptions) -> String {
+    let mut lines = vec![];
+    tree_node(eq, options, &mut lines, &mut 0);
+    format!("digraph \"{}\" {{\n{}}}\n", escape(&options.graph_name), lines.concat())
+}
+
+/// Adds the lines of the node and its operands and returns its identifier.
+fn tree_node(eq: &Equation, options: &DotOptions, lines: &mut Vec<String>, next: &mut usize) -> usize {
+    let id = *next;
+    *next += 1;
+    lines.push(format!("    n{} {};\n", id, options.node(eq, false)));
+    if eq.as_constant().is_some() || matches!(*eq, Equation::Var(_)) {
+        return id;
+    }
+    for inner in eq.inners() {
+        let child = tree_node(inner, options, lines, next);
+        lines.push(format!("    n{} -> n{};\n", id, child));
+    }
+    id
+}
+
+/// Returns the graph of the circuit computing the equation, the equal subterms being a single
+/// gate and the sums and products of a single operand being skipped.
+pub fn to_dot_dag(eq: &Equation, options: &DotOptions) -> String {
+    let mut lines = vec![];
+    dag_node(eq, options, &mut lines, &mut HashMap::new());
+    format!("digraph \"{}\" {{\n    rankdir=BT;\n{}}}\n", escape(&options.graph_name), lines.concat())
+}
+
+/// Adds the lines of the gate if it is not already drawn and returns its identifier.
+fn dag_node(
+    eq: &Equation,
+    options: &DotOptions,
+    lines: &mut Vec<String>,
+    ids: &mut HashMap<Equation, usize>,
+) -> usize {
+    let eq = eq.unwrap_single();
+    if let Some(&id) = ids.get(eq) {
+        return id;
+    }
+    let inners = match *eq {
+        Equation::Sum(_) | Equation::Prod(_) | Equation::Not(_) => eq.inners(),
+        Equation::Var(_) => vec![],
+    };
+    let children = inners
+        .into_iter()
+        .map(|inner| dag_node(inner, options, lines, ids))
+        .collect::<Vec<usize>>();
+    let id = ids.len();
+    ids.insert(eq.clone(), id);
+    lines.push(format!("    n{} {};\n", id, options.node(eq, true)));
+    for child in children {
+        lines.push(format!("    n{} -> n{};\n", child, id));
+    }
+    id
+}
+
+#[cfg(test)]
+mod tests_dot {
+    use super::*;
+
+    #[test]
+    fn test_tree() {
+        let dot = to_dot_tree(&Equation::from("a * !b".to_string()), &DotOptions::new("eq"));
+        assert_eq!(
+            dot,
+            "digraph \"eq\" {\n    n0 [label=\"+\", shape=circle];\n    n1 [label=\"*\", shape=circle];\n    \
+             n2 [label=\"a\", shape=box];\n    n1 -> n2;\n    n3 [label=\"!\", shape=circle];\n    \
+             n4 [label=\"b\", shape=box];\n    n3 -> n4;\n    n1 -> n3;\n    n0 -> n1;\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_dag() {
+        let mut options = DotOptions::new("circuit");
+        options.depth = true;
+        options.size = true;
+        let eq = Equation::from("a * !b + c * !b + a * !b".to_string());
+        let dot = to_dot_dag(&eq, &options);
+        // a, b, !b, a * !b, c, c * !b and the sum
+        assert_eq!(dot.matches("label=").count(), 7);
+        let constants = to_dot_tree(&Equation::from("a * 0".to_string()), &DotOptions::new("c"));
+        assert!(constants.contains("n3 [label=\"0\", shape=box];\n"));
+        assert!(dot.contains("n2 [label=\"NOT\\ndepth 2\\nsize 2\", shape=invtriangle];\n"));
+        assert!(dot.contains("label=\"OR\\ndepth 4\\nsize 13\""));
+        assert_eq!(dot.matches("-> n3;").count(), 2);
+    }
+
+    #[test]
+    fn test_escaping() {
+        let eq = Equation::var("a\\\"b");
+        let dot = to_dot_tree(&eq, &DotOptions::new("my \"graph\""));
+        assert_eq!(dot, "digraph \"my \\\"graph\\\"\" {\n    n0 [label=\"a\\\\\\\"b\", shape=box];\n}\n");
+    }
+}
@@ -1,19 +1,41 @@
-use lexer::EqLexer;
+use lexer::{Dialect, EqLexer};
 use parser::EqParser;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap};
 use std::fmt;
 use std::mem;
+use std::ops;
 use rand::random;
-use std::iter::FromIterator;
-use mccluskey::PrimeImplicant;
-use mccluskey::mccluskey;
+use mccluskey::{PrimeImplicant, Present};
+use mccluskey::mccluskey_checked;
+use compiled::{Compiled, MAX_TABLE_VARS};
+use bdd;
+use bdd::{Bdd, Models};
+use num_bigint::BigUint;
+use fold::Fold;
+use trace::Trace;
+use simplify::{Form, Pass, SimplifyOptions};
 
-#[derive(Debug, Clone, PartialEq)]
+/// Builds an equation from its text, eg. bexpr!(a * !b + c). The tokens are only stringified,
+/// the text being parsed at runtime with Equation::from, so a syntax error panics when the macro
+/// is evaluated rather than failing to compile.
+#[macro_export]
+macro_rules! bexpr {
+    ($($t:tt)*) => {
+        $crate::equation::Equation::from(stringify!($($t)*).to_string())
+    };
+}
+
+/// With serde, an equation is written as {"sum": [{"prod": [{"var": "a"}, {"not": {"var": "b"}}]}]}.
+/// The order of the variants is the canonical order : variables first, then negations, products
+/// and sums.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum Equation {
-    Sum(Box<Sum>),
-    Prod(Box<Prod>),
-    Not(Box<Not>),
     Var(String),
+    Not(Box<Not>),
+    Prod(Box<Prod>),
+    Sum(Box<Sum>),
 }
 
 impl Equation {
@@ -21,57 +43,266 @@ impl Equation {
         EqParser::new(EqLexer::new(text).get_tokens().unwrap()).parse()
     }
 
-    pub fn remove_simplified(&mut self) {
-        match self {
-            &mut Equation::Sum(ref mut s) => s.remove_simplified(),
-            _ => (),
-        }
+    /// Same as from but returns an error when the text is not a valid equation.
+    pub fn parse(text : &str) -> Result<Self, String> {
+        EqParser::new(EqLexer::new(text.to_string()).get_tokens()?).try_parse()
     }
 
-    pub fn reconstruct(&mut self){
-        match self {
-            &mut Equation::Sum(ref mut s) => {
-                let mut new_rep = mem::replace(&mut s.already_simplified, vec![]);
-                s.inner.append(&mut new_rep);
-                for p in s.inner.iter_mut(){
-                    p.reconstruct();
+    /// Parses the textbook notation where adjacent literals are a product, eg. ab' + c.
+    pub fn from_juxtaposition(text: String) -> Self {
+        EqParser::new(EqLexer::new(text).get_tokens().unwrap()).with_juxtaposition().parse()
+    }
+
+    pub fn var(name : &str) -> Self {
+        Equation::Var(name.to_string())
+    }
+
+    /// Returns the product of the equations, true if there is none.
+    pub fn all<I: IntoIterator<Item = Equation>>(eqs : I) -> Self {
+        Equation::Prod(Box::new(Prod::new(eqs.into_iter().collect())))
+    }
+
+    /// Returns the sum of the equations, false if there is none.
+    pub fn any<I: IntoIterator<Item = Equation>>(eqs : I) -> Self {
+        Equation::Sum(Box::new(Sum::new(eqs.into_iter().collect())))
+    }
+
+    /// Parses a Verilog expression such as ~a & (b ^ c[2]).
+    pub fn from_verilog(text: String) -> Result<Self, String> {
+        EqParser::new(EqLexer::with_dialect(text, Dialect::Verilog).get_tokens()?).try_parse()
+    }
+
+    /// Parses Verilog assign statements and returns the assigned names with their equations.
+    pub fn from_verilog_assigns(text: String) -> Result<Vec<(String, Equation)>, String> {
+        EqParser::new(EqLexer::with_dialect(text, Dialect::Verilog).get_tokens()?).assignments()
+    }
+
+    pub fn eval(&self, vars: &HashMap<String, bool>) -> bool {
+        self.try_eval(vars).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as eval but returns an error instead of panicking when a variable is not bound.
+    pub fn try_eval(&self, vars: &HashMap<String, bool>) -> Result<bool, String> {
+        match *self {
+            Equation::Sum(ref s) => {
+                for inner in s.inner.iter() {
+                    if inner.try_eval(vars)? {
+                        return Ok(true);
+                    }
                 }
-            },
-            &mut Equation::Prod(ref mut p) => {
-                for p in p.inner.iter_mut(){
-                    p.reconstruct();
+                Ok(false)
+            }
+            Equation::Prod(ref p) => {
+                for inner in p.inner.iter() {
+                    if !inner.try_eval(vars)? {
+                        return Ok(false);
+                    }
                 }
-            },
-            &mut Equation::Not(ref mut n) => n.inner.reconstruct(),
-            _ => ()
+                Ok(true)
+            }
+            Equation::Not(ref n) => n.inner.try_eval(vars).map(|v| !v),
+            Equation::Var(ref e) => vars.get(e).cloned().ok_or_else(|| format!("var not found : {}", e)),
         }
     }
 
-    pub fn len_removed(&mut self) -> usize{
-        match self {
-            &mut Equation::Sum(ref mut s) => s.already_simplified.len(),
-            _ => 0,
+    /// Three valued evaluation where Present::Any is an unknown value. This is the pessimistic
+    /// syntactic mode : a + !a is unknown when a is.
+    pub fn eval_ternary(&self, vars: &HashMap<String, Present>) -> Present {
+        match *self {
+            Equation::Sum(ref s) => s.inner.iter()
+                .fold(Present::No, |acc, inner| acc.or(inner.eval_ternary(vars))),
+            Equation::Prod(ref p) => p.inner.iter()
+                .fold(Present::Yes, |acc, inner| acc.and(inner.eval_ternary(vars))),
+            Equation::Not(ref n) => n.inner.eval_ternary(vars).negate(),
+            Equation::Var(ref e) => *vars.get(e)
+                .unwrap_or_else(|| panic!("var not found : {}", e)),
         }
     }
 
-    pub fn eval(&self, vars: &HashMap<String, bool>) -> bool {
-        match self {
-            &Equation::Sum(ref s) => s.inner.iter().any(|inner| inner.eval(&vars)),
-            &Equation::Prod(ref p) => p.inner.iter().all(|inner| inner.eval(&vars)),
-            &Equation::Not(ref n) => !n.inner.eval(&vars),
-            &Equation::Var(ref e) => *vars.get(e)
-                .unwrap_or_else(||panic!(format!("var not found : {}", e))),
+    /// Three valued evaluation which is unknown only if the result really depends on the unknown
+    /// variables, by cofactoring on the known ones.
+    pub fn eval_ternary_exact(&self, vars: &HashMap<String, Present>) -> Present {
+        let (mut bdd, mut root) = Bdd::from_eq(self);
+        for level in 0..bdd.vars().len() {
+            let value = *vars.get(&bdd.vars()[level])
+                .unwrap_or_else(|| panic!("var not found : {}", bdd.vars()[level]));
+            if value != Present::Any {
+                root = bdd.restrict(root, level, value == Present::Yes);
+            }
+        }
+        match root {
+            bdd::TRUE => Present::Yes,
+            bdd::FALSE => Present::No,
+            _ => Present::Any,
+        }
+    }
+
+    /// The constants are the empty product (true) and the empty sum (false).
+    pub fn constant(value : bool) -> Self {
+        match value {
+            true => Equation::Prod(Box::new(Prod::new(vec![]))),
+            false => Equation::Sum(Box::new(Sum::new(vec![]))),
+        }
+    }
+
+    /// Returns the value of the equation if it is a constant.
+    pub fn as_constant(&self) -> Option<bool> {
+        match *self {
+            Equation::Prod(ref p) if p.inner.is_empty() => Some(true),
+            Equation::Sum(ref s) if s.inner.is_empty() => Some(false),
+            _ => None,
         }
     }
 
+    /// Replaces every occurrence of the variable by the given equation.
+    pub fn substitute(&self, var : &str, by : &Equation) -> Equation {
+        self.replace_vars(&|v| match v == var {
+            true => Some(by.clone()),
+            false => None,
+        })
+    }
+
+    /// Replaces simultaneously every variable in subs by its equation.
+    pub fn compose(&self, subs : &HashMap<String, Equation>) -> Equation {
+        self.replace_vars(&|v| subs.get(v).cloned())
+    }
+
+    /// Sets the given variables and returns the simplified equation of the remaining ones.
+    pub fn assign(&self, vars : &HashMap<String, bool>) -> Equation {
+        self.replace_vars(&|v| vars.get(v).map(|&value| Equation::constant(value)))
+            .propagate_constants()
+            .complete_simplify()
+    }
+
+    /// Shannon cofactor : the simplified equation with the variable set to value.
+    pub fn cofactor(&self, var : &str, value : bool) -> Equation {
+        let mut vars = HashMap::new();
+        vars.insert(var.to_string(), value);
+        self.assign(&vars)
+    }
+
+    /// Existential quantification : true if there is a value of the variables making it true.
+    pub fn exists(&self, vars : &[&str]) -> Equation {
+        vars.iter().fold(self.clone(), |eq, var| Equation::Sum(Box::new(Sum::new(vec![
+            eq.cofactor(var, false),
+            eq.cofactor(var, true),
+        ]))).propagate_constants().complete_simplify())
+    }
+
+    /// Universal quantification : true if every value of the variables makes it true.
+    pub fn forall(&self, vars : &[&str]) -> Equation {
+        vars.iter().fold(self.clone(), |eq, var| Equation::Prod(Box::new(Prod::new(vec![
+            eq.cofactor(var, false),
+            eq.cofactor(var, true),
+        ]))).propagate_constants().complete_simplify())
+    }
+
+    /// Boolean difference with respect to the variable, true when flipping it flips the result :
+    /// df/dx = f(x=0) ^ f(x=1)
+    pub fn boolean_difference(&self, var : &str) -> Equation {
+        let (low, high) = (self.cofactor(var, false), self.cofactor(var, true));
+        let not = |eq : &Equation| Equation::Not(Box::new(Not::new(eq.clone())));
+        Equation::Sum(Box::new(Sum::new(vec![
+            Equation::Prod(Box::new(Prod::new(vec![low.clone(), not(&high)]))),
+            Equation::Prod(Box::new(Prod::new(vec![not(&low), high]))),
+        ]))).propagate_constants().complete_simplify()
+    }
+
+    /// True if the value of the variable can change the result of the equation.
+    pub fn depends_on(&self, var : &str) -> bool {
+        self.boolean_difference(var).is_satisfiable()
+    }
+
+    /// Returns the variables the equation really depends on, unlike get_vars a*b + a*!b only
+    /// depends on a.
+    pub fn support(&self) -> Vec<&String> {
+        let vars = self.get_vars();
+        let (bdd, root) = Bdd::from_eq(self);
+        bdd.support(root).into_iter().map(|level| vars[level]).collect()
+    }
+
+    /// True if at least one assignment makes the equation true.
+    pub fn is_satisfiable(&self) -> bool {
+        let (_, root) = Bdd::from_eq(self);
+        root != bdd::FALSE
+    }
+
+    fn replace_vars(&self, by : &dyn Fn(&String) -> Option<Equation>) -> Equation {
+        self.map(&mut |eq| match eq {
+            Equation::Var(v) => by(&v).unwrap_or(Equation::Var(v)),
+            e => e,
+        })
+    }
+
+    /// Removes the constants from the equation, which is either a constant or contains none.
+    pub fn propagate_constants(self) -> Equation {
+        self.rewrite(&mut |eq| match eq {
+            Equation::Sum(_) | Equation::Prod(_) => {
+                // true absorbs a sum and false a product
+                let absorbing = matches!(eq, Equation::Sum(_));
+                let mut inner = eq.into_inners();
+                if inner.iter().any(|i| i.as_constant() == Some(absorbing)) {
+                    return Equation::constant(absorbing);
+                }
+                inner.retain(|i| i.as_constant().is_none());
+                match (inner.len(), absorbing) {
+                    (1, _) => inner.pop().unwrap(),
+                    (_, true) => Equation::any(inner),
+                    (_, false) => Equation::all(inner),
+                }
+            }
+            Equation::Not(n) => match n.inner.as_constant() {
+                Some(value) => Equation::constant(!value),
+                None => Equation::Not(n),
+            },
+            v => v,
+        })
+    }
+
+    /// Returns the same tree with nested sums and products flattened and their operands sorted
+    /// and deduplicated, so that two equations differing only by the order of their operands
+    /// become equal.
+    pub fn canonicalize(self) -> Equation {
+        self.rewrite(&mut |eq| match eq {
+            Equation::Sum(_) | Equation::Prod(_) => {
+                let is_sum = matches!(eq, Equation::Sum(_));
+                let mut inner = vec![];
+                for i in eq.into_inners() {
+                    match (i, is_sum) {
+                        (i @ Equation::Sum(_), true) | (i @ Equation::Prod(_), false) => {
+                            inner.append(&mut i.into_inners())
+                        }
+                        (i, _) => inner.push(i),
+                    }
+                }
+                inner.sort();
+                inner.dedup();
+                match (inner.len(), is_sum) {
+                    (1, _) => inner.pop().unwrap(),
+                    (_, true) => Equation::any(inner),
+                    (_, false) => Equation::all(inner),
+                }
+            }
+            e => e,
+        })
+    }
+
     pub fn mccluskey(&mut self){
-        let new_sum = match self {
-            &mut Equation::Sum(ref mut s) => {
-                let res = mccluskey(s.get_primes_implicants());
-                s.inner = res;
+        self.mccluskey_checked(&mut || true);
+    }
+
+    /// Same as mccluskey, leaving the equation unchanged and returning false if check stops it.
+    pub fn mccluskey_checked(&mut self, check : &mut dyn FnMut() -> bool) -> bool {
+        match self {
+            &mut Equation::Sum(ref mut s) => match mccluskey_checked(s.get_primes_implicants(), check) {
+                Some(res) => {
+                    s.inner = res;
+                    true
+                },
+                None => false,
             },
-            _ => ()
-        };
+            _ => true
+        }
     }
 
     pub fn inners(&self) -> Vec<&Equation> {
@@ -83,13 +314,112 @@ impl Equation {
         }
     }
 
-    /// True if the truthtables are the same.
+    /// True if the truthtables are the same, a variable missing from one of the equations is
+    /// one it does not depend on.
     pub fn compare_random_values(&self, other : &Equation, tests : usize) {
-        let vars = other.get_vars();
-        assert_eq!(vars.len(), self.get_vars().len());
-        for _ in 0..tests {
-            let vals = HashMap::from_iter(vars.iter().map(|&v|(v.clone(), random())));
-            assert_eq!(other.eval(&vals), self.eval(&vals));
+        let vars = self.union_vars(other);
+        let (this, other) = (Compiled::with_vars(self, &vars), Compiled::with_vars(other, &vars));
+        let mut regs = vec![];
+        for _ in 0..tests.div_ceil(64) {
+            let words = vars.iter().map(|_| random()).collect::<Vec<u64>>();
+            assert_eq!(other.eval_words(&words, &mut regs), this.eval_words(&words, &mut regs));
+        }
+    }
+
+    /// Returns the truth table packed in words, the variable i taking the bit i of the number of
+    /// the assignment, in the order of get_vars, failing above MAX_TABLE_VARS variables.
+    pub fn truth_table(&self) -> Result<Vec<u64>, String> {
+        Compiled::new(self).truth_table()
+    }
+
+    /// Returns the sorted variables appearing in any of the two equations.
+    fn union_vars(&self, other : &Equation) -> Vec<String> {
+        let mut vars = self.get_owned_vars();
+        vars.append(&mut other.get_owned_vars());
+        vars.sort();
+        vars.dedup();
+        vars
+    }
+
+    /// True if both equations have the same value for every assignment of their variables, using
+    /// the truth tables and the BDD when they are too large.
+    pub fn is_equivalent(&self, other : &Equation) -> bool {
+        let vars = self.union_vars(other);
+        if vars.len() <= MAX_TABLE_VARS {
+            return Compiled::with_vars(self, &vars).truth_table() == Compiled::with_vars(other, &vars).truth_table();
+        }
+        let mut bdd = Bdd::new(vars);
+        bdd.build(self) == bdd.build(other)
+    }
+
+    /// Returns the number of assignments of the variables for which the equation is true.
+    pub fn count_models(&self) -> BigUint {
+        if let Some(impls) = self.disjoint_cover() {
+            return impls.iter().fold(BigUint::from(0u32), |acc, i| acc + (BigUint::from(1u32) << i.nb_any()));
+        }
+        let (bdd, root) = Bdd::from_eq(self);
+        bdd.count(root)
+    }
+
+    /// Returns the probability of the equation being true when every variable is true with the
+    /// given probability, independently of the others.
+    pub fn probability(&self, probs : &HashMap<String, f64>) -> f64 {
+        let probs = self.get_vars().into_iter()
+            .map(|v| *probs.get(v).unwrap_or_else(|| panic!("var not found : {}", v)))
+            .collect::<Vec<f64>>();
+        if let Some(impls) = self.disjoint_cover() {
+            return impls.iter().map(|i| i.probability(&probs)).sum();
+        }
+        let (bdd, root) = Bdd::from_eq(self);
+        bdd.probability(root, &probs)
+    }
+
+    /// Lazily enumerates the satisfying assignments as disjoint cubes over the variables of
+    /// get_vars, use Models::expanded to get every single assignment.
+    pub fn models(&self) -> Models {
+        let (bdd, root) = Bdd::from_eq(self);
+        Models::new(bdd, root)
+    }
+
+    /// Returns the implicants of the equation if it is a sum of pairwise disjoint products.
+    fn disjoint_cover(&self) -> Option<Vec<PrimeImplicant>> {
+        let terms = match *self {
+            Equation::Sum(ref s) => s.inner.iter().collect(),
+            _ => vec![self],
+        };
+        if !terms.iter().all(|t| t.is_cube()) {
+            return None;
+        }
+        let vars = self.get_vars();
+        let impls = terms.into_iter()
+            .filter(|t| match **t {
+                Equation::Prod(ref p) => !p.is_contradiction(),
+                _ => true,
+            })
+            .map(|t| PrimeImplicant::from_eq(t, &vars))
+            .collect::<Vec<PrimeImplicant>>();
+        for i in 0..impls.len() {
+            if impls[i + 1..].iter().any(|other| !impls[i].is_disjoint(other)) {
+                return None;
+            }
+        }
+        Some(impls)
+    }
+
+    /// True for a variable or its negation.
+    pub fn is_literal(&self) -> bool {
+        match *self {
+            Equation::Var(_) => true,
+            Equation::Not(ref n) => matches!(n.inner, Equation::Var(_)),
+            _ => false,
+        }
+    }
+
+    /// True for a literal or a product of literals.
+    pub fn is_cube(&self) -> bool {
+        match *self {
+            Equation::Prod(ref p) => p.inner.iter().all(|i| i.is_literal()),
+            _ => self.is_literal(),
         }
     }
 
@@ -102,19 +432,14 @@ impl Equation {
         }
     }
 
+    /// Simplifies into a minimal sum of products with the default passes of SimplifyOptions.
     pub fn complete_simplify(self) -> Self {
-        let ancient_nb_var = self.get_vars().len();
-        let mut old_self = self;
-        let mut new_self = old_self.clone().simplified();
-        while new_self != old_self {
-            //println!("self len : {}", format!("{}", new_self).len());
-            old_self = new_self;
-            new_self = old_self.clone().simplified();
-            new_self.remove_simplified();
-        }
-        new_self.reconstruct();
-        new_self.mccluskey();
-        new_self
+        self.complete_simplify_traced(&mut Trace::disabled())
+    }
+
+    /// Same as complete_simplify, recording every rewrite in the trace.
+    pub fn complete_simplify_traced(self, trace : &mut Trace) -> Self {
+        self.simplify_with_traced(&SimplifyOptions::default(), trace)
     }
 
     pub fn is_product(&self) -> bool {
@@ -124,45 +449,22 @@ impl Equation {
         }
     }
 
-    pub fn is_simplified(&self, depth : usize) -> bool {
-        match self {
-            &Equation::Sum(ref s) => s
-                .inner
-                .iter()
-                .all(|inner| inner.is_simplified(depth + 1)),
-            &Equation::Prod(ref p) => p
-                .inner
-                .iter()
-                .all(|inner| inner.is_simplified(depth + 1)),
-            &Equation::Not(ref n) => n.inner.is_simplified(depth + 1),
-            &Equation::Var(_) => depth < 5,
-        }
-    }
-
-    /// Simplifies using simple basic rules.
+    /// Simplifies using simple basic rules : De Morgan's laws, the distribution of the products
+    /// over the sums and the flattening of the nested operators.
     pub fn simplified(self) -> Self {
-        match self {
-            Equation::Sum(s) => s.simplified(),
-            Equation::Prod(p) => p.simplified(),
-            Equation::Not(n) => n.simplified(),
-            v => v,
-        }
+        let mut options = SimplifyOptions::new(vec![Pass::Nnf, Pass::Distribution, Pass::Flatten]);
+        options.form = Form::MultiLevel;
+        self.simplify_with(&options)
     }
 
     /// Returns a list of the names of the variables.
     pub fn get_vars(&self) -> Vec<&String> {
-        let mut hs = HashSet::new();
         match self {
             &Equation::Sum(ref s) => s.get_vars(),
             &Equation::Prod(ref p) => p.get_vars(),
             &Equation::Not(ref n) => n.inner.get_vars(),
             &Equation::Var(ref e) => vec![e],
-        }.into_iter()
-            .map(|var| hs.insert(var))
-            .for_each(drop);
-        let mut to_ret = hs.into_iter().collect::<Vec<&String>>();
-        to_ret.sort();
-        to_ret
+        }
     }
 
     /// Returns a list of the names of the variables.
@@ -179,22 +481,30 @@ impl Equation {
 
     /// Returns the depth of the tree
     pub fn depth(&self, so_far: usize) -> usize {
-        match self {
-            &Equation::Sum(ref s) => s
-                .inner
-                .iter()
-                .map(|inner| inner.depth(so_far + 1))
-                .max()
-                .unwrap_or(0),
-            &Equation::Prod(ref p) => p
-                .inner
-                .iter()
-                .map(|inner| inner.depth(so_far + 1))
-                .max()
-                .unwrap_or(0),
-            &Equation::Not(ref n) => n.inner.depth(so_far + 1),
-            &Equation::Var(_) => so_far + 1,
-        }
+        self.fold(&mut Height).map_or(0, |height| so_far + height)
+    }
+}
+
+/// Height of the tree, None for the empty sums and products.
+struct Height;
+
+impl Fold for Height {
+    type Output = Option<usize>;
+
+    fn sum(&mut self, inner: Vec<Option<usize>>) -> Option<usize> {
+        inner.into_iter().max().and_then(|h| h.map(|h| h + 1))
+    }
+
+    fn prod(&mut self, inner: Vec<Option<usize>>) -> Option<usize> {
+        self.sum(inner)
+    }
+
+    fn not(&mut self, inner: Option<usize>) -> Option<usize> {
+        inner.map(|h| h + 1)
+    }
+
+    fn var(&mut self, _: &str) -> Option<usize> {
+        Some(1)
     }
 }
 
@@ -227,22 +537,78 @@ impl fmt::Display for Equation {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+impl ops::BitAnd for Equation {
+    type Output = Equation;
+
+    /// a & b & c gives a single product.
+    fn bitand(self, other : Equation) -> Equation {
+        match self {
+            Equation::Prod(mut p) => {
+                p.inner.push(other);
+                Equation::Prod(p)
+            }
+            s => Equation::all(vec![s, other]),
+        }
+    }
+}
+
+impl ops::BitOr for Equation {
+    type Output = Equation;
+
+    /// a | b | c gives a single sum.
+    fn bitor(self, other : Equation) -> Equation {
+        match self {
+            Equation::Sum(mut s) => {
+                s.inner.push(other);
+                Equation::Sum(s)
+            }
+            s => Equation::any(vec![s, other]),
+        }
+    }
+}
+
+impl ops::BitXor for Equation {
+    type Output = Equation;
+
+    /// a ^ b => (a * !b) + (!a * b)
+    fn bitxor(self, other : Equation) -> Equation {
+        Equation::any(vec![
+            Equation::all(vec![self.clone(), !other.clone()]),
+            Equation::all(vec![!self, other]),
+        ])
+    }
+}
+
+impl ops::Not for Equation {
+    type Output = Equation;
+
+    fn not(self) -> Equation {
+        Equation::Not(Box::new(Not::new(self)))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
 pub struct Sum {
     inner: Vec<Equation>,
-    already_simplified : Vec<Equation>,
 }
 
 impl Sum {
     pub fn new(inner: Vec<Equation>) -> Self {
-        Sum { inner: inner , already_simplified : vec![]}
+        Sum { inner: inner }
     }
 
     /// Applyes the queen mccluskey algorithm to reduce the size of the sum.
+    /// The variables outside of the support are dropped from the implicants.
     pub fn get_primes_implicants(&self) -> (Vec<String>, Vec<PrimeImplicant>){
-        let vars = self.get_vars();
+        let vars = self.support();
         (vars.clone().into_iter().map(|i|i.clone()).collect(),
-         self.inner.iter().map(|ref i|PrimeImplicant::from_eq(i, &vars)).collect())
+         self.inner.iter()
+             .filter(|i| match i {
+                 Equation::Prod(p) => !p.is_contradiction(),
+                 _ => true,
+             })
+             .map(|i|PrimeImplicant::from_eq(i, &vars)).collect())
     }
 
 
@@ -253,53 +619,26 @@ impl Sum {
 
     /// Returns a list of the names of the variables.
     pub fn get_vars(&self) -> Vec<&String> {
-        let mut hs = HashSet::new();
-        self.inner.iter().flat_map(|inner| inner.get_vars().into_iter()).into_iter()
-            .map(|var| hs.insert(var))
-            .for_each(drop);
-        let mut to_ret = hs.into_iter().collect::<Vec<&String>>();
-        to_ret.sort();
-        to_ret
-    }
-
-    /// This must be called at the top level only.
-    pub fn remove_simplified(&mut self) {
-        let mut not_simp = vec![];
-        let mut simp = vec![];
-        for i in self.inner.iter_mut(){
-            if i.is_simplified(0){
-                simp.push( i.clone());
-            } else {
-                not_simp.push(i.clone());
-            }
-        }
-        self.inner = not_simp;
-        self.already_simplified.append(&mut simp);
+        self.inner.iter().flat_map(|inner| inner.get_vars())
+            .collect::<BTreeSet<&String>>()
+            .into_iter()
+            .collect()
     }
 
-    /// Sums can be simplified using two simple rules :
-    /// sum(a) = a
-    /// sum(a, sum(b, c)) = sum (a, b, c)
-    pub fn simplified(mut self) -> Equation {
-        let mut new_inner = vec![];
-        for old_i in self.inner {
-            let mut i = old_i.simplified();
-            match i {
-                Equation::Sum(ref mut s) => new_inner.append(&mut s.inner),
-                _ => new_inner.push(i),
-            };
-        }
-        self.inner = new_inner;
-        if self.inner.len() == 1 {
-            let ret = self.inner.into_iter().next().unwrap();
-            return ret.simplified();
-        } else {
-            return Equation::Sum(Box::new(self));
-        }
+    /// Returns the variables the sum really depends on.
+    pub fn support(&self) -> Vec<&String> {
+        let vars = self.get_vars();
+        let mut bdd = Bdd::new(vars.iter().map(|&v| v.clone()).collect());
+        let root = self.inner.iter().fold(bdd::FALSE, |acc, inner| {
+            let node = bdd.build(inner);
+            bdd.or(acc, node)
+        });
+        bdd.support(root).into_iter().map(|level| vars[level]).collect()
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
 pub struct Prod {
     inner: Vec<Equation>,
 }
@@ -333,66 +672,25 @@ impl Prod {
         new_inner.clone()
     }
 
-    pub fn factorise_for(mut self, i: usize) -> Equation {
-        let removed = self.inner.remove(i);
-        let mut new_sum_of_products = vec![];
-        for sub_sum_element in removed.into_inners() {
-            let mut new_inner = self.inner.clone();
-            new_inner.push(sub_sum_element);
-            new_sum_of_products.push(Equation::Prod(Box::new(Prod::new(new_inner))));
-        }
-        Equation::Sum(Box::new(Sum::new(new_sum_of_products))).complete_simplify()
-    }
-
-    pub fn flatten(mut self) -> Equation {
-        let mut new_inner = vec![];
-        for old_i in self.inner {
-            let mut i = old_i.simplified();
-            match i {
-                Equation::Prod(ref mut p) => new_inner.append(&mut p.inner),
-                _ => new_inner.push(i),
-            };
-        }
-        self.inner = new_inner;
-        if self.inner.len() == 1 {
-            let ret = self.inner.into_iter().next().unwrap();
-            return ret.simplified();
-        } else {
-            return Equation::Prod(Box::new(self));
-        }
-    }
-    /// Products can be simplified in the same way than the addition but we can also factorise :
-    /// a * (B + c + d) * e => (a * e * B) + (a * e * c) + (a * e * d)
-    ///
-    pub fn simplified(mut self) -> Equation {
-        self.inner = self
-            .inner
-            .into_iter()
-            .map(|inner| inner.simplified())
-            .collect();
-        for i in 0..self.inner.len() {
-            if mem::discriminant(&self.inner[i])
-                == mem::discriminant(&Equation::Sum(Box::new(Sum::new(vec![]))))
-            {
-                return self.factorise_for(i);
-            }
-        }
-        self.flatten()
+    /// True if the product of literals contains both a variable and its negation (eg. a * !a),
+    /// false if an operand is not a literal.
+    pub fn is_contradiction(&self) -> bool {
+        !self.inner.is_empty()
+            && self.inner.iter().all(|i| i.is_literal())
+            && self.removed_doublons().is_empty()
     }
 
     /// Returns a list of the names of the variables.
     pub fn get_vars(&self) -> Vec<&String> {
-        let mut hs = HashSet::new();
-        self.inner.iter().flat_map(|inner| inner.get_vars().into_iter()).into_iter()
-            .map(|var| hs.insert(var))
-            .for_each(drop);
-        let mut to_ret = hs.into_iter().collect::<Vec<&String>>();
-        to_ret.sort();
-        to_ret
+        self.inner.iter().flat_map(|inner| inner.get_vars())
+            .collect::<BTreeSet<&String>>()
+            .into_iter()
+            .collect()
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
 pub struct Not {
     inner: Equation,
 }
@@ -406,26 +704,6 @@ impl Not {
     pub fn get_only_var(&self) -> &String {
         self.inner.get_vars()[0]
     }
-
-    pub fn simplified(mut self) -> Equation {
-        self.inner = self.inner.complete_simplify();
-        match self.inner {
-            Equation::Not(box n) => n.inner.simplified(),
-            Equation::Sum(box s) => Equation::Prod(Box::new(Prod::new(
-                s.inner
-                    .into_iter()
-                    .map(|i| Equation::Not(Box::new(Not::new(i))))
-                    .collect(),
-            ))),
-            Equation::Prod(box s) => Equation::Sum(Box::new(Sum::new(
-                s.inner
-                    .into_iter()
-                    .map(|i| Equation::Not(Box::new(Not::new(i))))
-                    .collect(),
-            ))),
-            v => Equation::Not(Box::new(Not::new(v))),
-        }
-    }
 }
 
 #[cfg(test)]
@@ -603,4 +881,305 @@ mod tests_simplify {
         new_eq.compare_random_values(&eq, 1000);
     }
 
+    #[test]
+    fn test_equivalent() {
+        let eq = Equation::from("(a + b)*(!a+c)".to_string());
+        assert!(eq.is_equivalent(&eq.clone().complete_simplify()));
+        assert!(eq.is_equivalent(&Equation::from("!a*b + a*c".to_string())));
+        assert!(!eq.is_equivalent(&Equation::from("!a*b + c".to_string())));
+        assert!(Equation::from("a*b + a*!b".to_string()).is_equivalent(&Equation::from("a".to_string())));
+        let names = (0..70).map(|i| format!("x{}", i)).collect::<Vec<String>>();
+        let large = Equation::from(names.join(" * "));
+        assert!(large.is_equivalent(&Equation::from(names.iter().rev().cloned().collect::<Vec<String>>().join(" * "))));
+        assert!(!large.is_equivalent(&Equation::from(names[1..].join(" * "))));
+    }
+
+    #[test]
+    fn test_contradiction() {
+        let eq = Equation::from("a*!a + b".to_string());
+        assert_eq!(format!("{}", eq.complete_simplify()), "(b)");
+        let eq = Equation::from("(a + b)*(!a+c)".to_string());
+        let new_eq = eq.clone().complete_simplify();
+        new_eq.compare_random_values(&eq, 1000);
+    }
+
+}
+
+#[cfg(test)]
+mod tests_contradiction {
+    use super::*;
+
+    fn is_contradiction(text : &str) -> bool {
+        match Equation::from(text.to_string()).into_inners().pop().unwrap() {
+            Equation::Prod(p) => p.is_contradiction(),
+            _ => panic!("expected a product"),
+        }
+    }
+
+    #[test]
+    fn test_literals() {
+        assert!(is_contradiction("a * b * !a"));
+        assert!(!is_contradiction("a * b * a"));
+        assert!(!is_contradiction("a"));
+        assert!(!Prod::new(vec![]).is_contradiction());
+    }
+
+    #[test]
+    fn test_not_literals() {
+        assert!(!is_contradiction("a * (b + c) * !a"));
+        assert!(!is_contradiction("a * !(b * c)"));
+    }
+}
+
+#[cfg(test)]
+mod tests_count {
+    use super::*;
+
+    fn count_table(eq : &Equation) -> u32 {
+        eq.truth_table().unwrap().iter().map(|w| w.count_ones()).sum()
+    }
+
+    #[test]
+    fn test_count() {
+        for text in ["a*b + c", "a*b*c + !a*!b", "(a + b)*(!a+c) + d*!a", "a + !a", "a*!a"].iter() {
+            let eq = Equation::from(text.to_string());
+            assert_eq!(eq.count_models(), BigUint::from(count_table(&eq)));
+        }
+    }
+
+    #[test]
+    fn test_disjoint_cover() {
+        let eq = Equation::from("a*b + !a*c + a*!b*!c".to_string());
+        assert_eq!(eq.disjoint_cover().unwrap().len(), 3);
+        assert_eq!(eq.count_models(), BigUint::from(count_table(&eq)));
+        assert!(Equation::from("a*b + b*c".to_string()).disjoint_cover().is_none());
+        assert!(Equation::from("a*(b + c)".to_string()).disjoint_cover().is_none());
+    }
+
+    #[test]
+    fn test_many_vars() {
+        let text = (0..70).map(|i| format!("v{}", i)).collect::<Vec<String>>().join(" + ");
+        let eq = Equation::from(text);
+        assert_eq!(eq.count_models(), (BigUint::from(1u32) << 70) - BigUint::from(1u32));
+    }
+
+    #[test]
+    fn test_models() {
+        let eq = Equation::from("a*b + b*c".to_string());
+        let cover = Equation::Sum(Box::new(Sum::new(eq.models()
+            .map(|p| p.to_eq(&eq.get_vars()))
+            .collect())));
+        assert!(cover.is_equivalent(&eq));
+        assert_eq!(eq.models().expanded().count(), 3);
+    }
+
+    #[test]
+    fn test_probability() {
+        let mut probs = HashMap::new();
+        probs.insert("a".to_string(), 0.5);
+        probs.insert("b".to_string(), 0.25);
+        probs.insert("c".to_string(), 0.1);
+        assert_eq!(Equation::from("a*b + !a*c".to_string()).probability(&probs), 0.125 + 0.05);
+        assert_eq!(Equation::from("a + b".to_string()).probability(&probs), 1.0 - 0.5 * 0.75);
+    }
+}
+
+#[cfg(test)]
+mod tests_substitute {
+    use super::*;
+
+    #[test]
+    fn test_substitute() {
+        let eq = Equation::from("a * !b + c".to_string());
+        let new_eq = eq.substitute("b", &Equation::from("x + y".to_string()));
+        assert_eq!(format!("{}", new_eq), "((a * ! ((x) + (y))) + (c))");
+        let mut subs = HashMap::new();
+        subs.insert("a".to_string(), Equation::from("b".to_string()));
+        subs.insert("b".to_string(), Equation::from("a".to_string()));
+        assert!(eq.compose(&subs).is_equivalent(&Equation::from("b * !a + c".to_string())));
+    }
+
+    #[test]
+    fn test_assign() {
+        let eq = Equation::from("a * !b + c * d".to_string());
+        let mut vars = HashMap::new();
+        vars.insert("b".to_string(), false);
+        assert_eq!(format!("{}", eq.assign(&vars)), "(a + (c * d))");
+        vars.insert("a".to_string(), false);
+        vars.insert("c".to_string(), true);
+        assert_eq!(eq.assign(&vars), Equation::Var("d".to_string()));
+        vars.insert("d".to_string(), false);
+        assert_eq!(eq.assign(&vars).as_constant(), Some(false));
+        vars.insert("a".to_string(), true);
+        assert_eq!(eq.assign(&vars).as_constant(), Some(true));
+    }
+
+    #[test]
+    fn test_try_eval() {
+        let eq = Equation::from("a * b".to_string());
+        let mut vars = HashMap::new();
+        vars.insert("a".to_string(), true);
+        assert_eq!(eq.try_eval(&vars), Err("var not found : b".to_string()));
+        vars.insert("b".to_string(), true);
+        assert_eq!(eq.try_eval(&vars), Ok(true));
+    }
+}
+
+#[cfg(test)]
+mod tests_quantify {
+    use super::*;
+
+    #[test]
+    fn test_cofactor() {
+        let eq = Equation::from("a*b + !a*c".to_string());
+        assert_eq!(eq.cofactor("a", true), Equation::Var("b".to_string()));
+        assert_eq!(eq.cofactor("a", false), Equation::Var("c".to_string()));
+        assert_eq!(eq.cofactor("x", false), eq.clone().complete_simplify());
+    }
+
+    #[test]
+    fn test_quantifiers() {
+        let eq = Equation::from("a*b + !a*c".to_string());
+        assert!(eq.exists(&["a"]).is_equivalent(&Equation::from("b + c".to_string())));
+        assert!(eq.forall(&["a"]).is_equivalent(&Equation::from("b * c".to_string())));
+        assert_eq!(eq.exists(&["a", "b"]).as_constant(), Some(true));
+        assert_eq!(eq.forall(&["b", "c"]).as_constant(), Some(false));
+    }
+
+    #[test]
+    fn test_boolean_difference() {
+        let eq = Equation::from("a*b + !a*c".to_string());
+        assert!(eq.boolean_difference("a").is_equivalent(&Equation::from("b*!c + !b*c".to_string())));
+        assert!(eq.depends_on("c"));
+        let eq = Equation::from("a*b + a*!b".to_string());
+        assert!(eq.depends_on("a"));
+        assert!(!eq.depends_on("b"));
+    }
+}
+
+#[cfg(test)]
+mod tests_support {
+    use super::*;
+
+    #[test]
+    fn test_support() {
+        assert_eq!(Equation::from("a*b + a*!b".to_string()).support(), vec!["a"]);
+        assert_eq!(Equation::from("a*b + !a*c".to_string()).support(), vec!["a", "b", "c"]);
+        assert!(Equation::from("x + !x".to_string()).support().is_empty());
+    }
+
+    #[test]
+    fn test_implicants_width() {
+        let eq = Equation::from("a*b*x + a*b*!x + a*!b + c*!c".to_string()).simplified();
+        let (vars, impls) = match eq {
+            Equation::Sum(ref s) => s.get_primes_implicants(),
+            _ => panic!("expected a sum"),
+        };
+        assert_eq!(vars, vec!["a"]);
+        assert!(impls.iter().all(|i| i.get_string().len() == 1));
+        let new_eq = eq.clone().complete_simplify();
+        assert_eq!(new_eq.get_vars(), vec!["a"]);
+        assert!(new_eq.is_equivalent(&eq));
+    }
+}
+
+#[cfg(test)]
+mod tests_ternary {
+    use super::*;
+
+    fn values(vals : &[(&str, Present)]) -> HashMap<String, Present> {
+        vals.iter().map(|&(v, p)| (v.to_string(), p)).collect()
+    }
+
+    #[test]
+    fn test_pessimistic() {
+        let eq = Equation::from("a * b + !a * c".to_string());
+        let vars = values(&[("a", Present::Yes), ("b", Present::No), ("c", Present::Any)]);
+        assert_eq!(eq.eval_ternary(&vars), Present::No);
+        let vars = values(&[("a", Present::Any), ("b", Present::Yes), ("c", Present::Yes)]);
+        assert_eq!(eq.eval_ternary(&vars), Present::Any);
+        let vars = values(&[("a", Present::No), ("b", Present::Any), ("c", Present::Yes)]);
+        assert_eq!(eq.eval_ternary(&vars), Present::Yes);
+    }
+
+    #[test]
+    fn test_exact() {
+        let eq = Equation::from("a * b + !a * c".to_string());
+        let vars = values(&[("a", Present::Any), ("b", Present::Yes), ("c", Present::Yes)]);
+        assert_eq!(eq.eval_ternary_exact(&vars), Present::Yes);
+        let vars = values(&[("a", Present::Any), ("b", Present::Yes), ("c", Present::No)]);
+        assert_eq!(eq.eval_ternary_exact(&vars), Present::Any);
+        let eq = Equation::from("a + !a".to_string());
+        let vars = values(&[("a", Present::Any)]);
+        assert_eq!(eq.eval_ternary(&vars), Present::Any);
+        assert_eq!(eq.eval_ternary_exact(&vars), Present::Yes);
+    }
+}
+
+#[cfg(test)]
+mod tests_builder {
+    use super::*;
+
+    #[test]
+    fn test_operators() {
+        let (a, b, c) = (Equation::var("a"), Equation::var("b"), Equation::var("c"));
+        let eq = a.clone() & !b.clone() | c.clone() & a.clone() & b.clone();
+        assert_eq!(format!("{}", eq), "((a * ! b) + (c * a * b))");
+        assert!((a.clone() ^ b.clone()).is_equivalent(&Equation::from("a*!b + !a*b".to_string())));
+        assert!((!(a ^ b) | c).is_equivalent(&Equation::from("a*b + !a*!b + c".to_string())));
+    }
+
+    #[test]
+    fn test_all_any() {
+        let vars = ["a", "b", "c"].iter().map(|v| Equation::var(v));
+        assert_eq!(format!("{}", Equation::all(vars.clone())), "(a * b * c)");
+        assert_eq!(format!("{}", Equation::any(vars)), "(a + b + c)");
+        assert_eq!(Equation::all(vec![]).as_constant(), Some(true));
+        assert_eq!(Equation::any(vec![]).as_constant(), Some(false));
+    }
+
+    #[test]
+    fn test_macro() {
+        assert_eq!(bexpr!(a * !b + c), Equation::from("a * !b + c".to_string()));
+        assert_eq!(bexpr!(I & !B | (A + B) and (c + a)), Equation::from("I & !B | (A + B) and (c + a)".to_string()));
+    }
+}
+
+#[cfg(test)]
+mod tests_canonical {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_canonicalize() {
+        let eq = Equation::from("(c + !b) * a * a + b * (d * a)".to_string()).canonicalize();
+        assert_eq!(format!("{}", eq), "((a * b * d) + (a * (c + ! b)))");
+        let other = Equation::from("a * d * b + a * (!b + c)".to_string()).canonicalize();
+        assert_eq!(eq, other);
+        let mut set = HashSet::new();
+        set.insert(eq);
+        assert!(set.contains(&other));
+        assert_eq!(Equation::from("a + a".to_string()).canonicalize(), Equation::var("a"));
+        assert_eq!(Equation::from("1 * (0 + 0)".to_string()).canonicalize(), Equation::constant(false));
+    }
+
+    #[test]
+    fn test_ordering() {
+        let mut eqs = vec![bexpr!(a + b), bexpr!(!a), bexpr!(a * b), bexpr!(b), bexpr!(a)]
+            .into_iter()
+            .map(|eq| eq.canonicalize())
+            .collect::<Vec<Equation>>();
+        eqs.sort();
+        let printed = eqs.iter().map(|eq| format!("{}", eq)).collect::<Vec<String>>();
+        assert_eq!(printed, vec!["a", "b", "! a", "(a * b)", "(a + b)"]);
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let text = "a * b * !c + a * !b * c + !a * b * c + a * b * c + d * !a + d * (b + !c)";
+        let first = format!("{}", Equation::from(text.to_string()).complete_simplify());
+        for _ in 0..5 {
+            assert_eq!(format!("{}", Equation::from(text.to_string()).complete_simplify()), first);
+        }
+    }
 }
@@ -0,0 +1,141 @@
+//! Generic traversals of equations, so that new analyses and rewrite rules do not have to match
+//! the four variants by hand.
+
+use equation::Equation;
+
+/// Bottom-up computation : every method receives the results of the operands of the node.
+pub trait Fold {
+    type Output;
+
+    fn sum(&mut self, inner: Vec<Self::Output>) -> Self::Output;
+    fn prod(&mut self, inner: Vec<Self::Output>) -> Self::Output;
+    fn not(&mut self, inner: Self::Output) -> Self::Output;
+    fn var(&mut self, name: &str) -> Self::Output;
+}
+
+impl Equation {
+    /// Computes the fold from the leaves to the root.
+    pub fn fold<F: Fold>(&self, f: &mut F) -> F::Output {
+        match *self {
+            Equation::Var(ref v) => f.var(v),
+            Equation::Not(_) => {
+                let inner = self.inners()[0].fold(f);
+                f.not(inner)
+            }
+            Equation::Sum(_) => {
+                let inner = self.inners().into_iter().map(|i| i.fold(f)).collect();
+                f.sum(inner)
+            }
+            Equation::Prod(_) => {
+                let inner = self.inners().into_iter().map(|i| i.fold(f)).collect();
+                f.prod(inner)
+            }
+        }
+    }
+
+    /// Calls f on every node, parents before their operands.
+    pub fn walk<F: FnMut(&Equation)>(&self, f: &mut F) {
+        f(self);
+        if let Equation::Var(_) = *self {
+            return;
+        }
+        for inner in self.inners() {
+            inner.walk(f);
+        }
+    }
+
+    /// Skips the sums and products containing only one element.
+    pub fn unwrap_single(&self) -> &Equation {
+        match *self {
+            Equation::Sum(_) | Equation::Prod(_) if self.inners().len() == 1 => {
+                self.inners()[0].unwrap_single()
+            }
+            _ => self,
+        }
+    }
+
+    /// Returns a copy where every node is replaced by f, operands first.
+    pub fn map<F: FnMut(Equation) -> Equation>(&self, f: &mut F) -> Equation {
+        self.clone().rewrite(f)
+    }
+
+    /// Replaces every node by f, operands first, so that f sees the rewritten operands.
+    pub fn rewrite<F: FnMut(Equation) -> Equation>(self, f: &mut F) -> Equation {
+        let rebuilt = match self {
+            Equation::Var(_) => self,
+            Equation::Not(_) => !self.into_inners().pop().unwrap().rewrite(f),
+            Equation::Sum(_) | Equation::Prod(_) => {
+                let is_sum = matches!(self, Equation::Sum(_));
+                let inner = self
+                    .into_inners()
+                    .into_iter()
+                    .map(|i| i.rewrite(f))
+                    .collect::<Vec<Equation>>();
+                match is_sum {
+                    true => Equation::any(inner),
+                    false => Equation::all(inner),
+                }
+            }
+        };
+        f(rebuilt)
+    }
+}
+
+#[cfg(test)]
+mod tests_fold {
+    use super::*;
+
+    /// Counts the operators of an equation.
+    struct Size;
+
+    impl Fold for Size {
+        type Output = usize;
+
+        fn sum(&mut self, inner: Vec<usize>) -> usize {
+            inner.iter().sum::<usize>() + inner.len().saturating_sub(1)
+        }
+        fn prod(&mut self, inner: Vec<usize>) -> usize {
+            inner.iter().sum::<usize>() + inner.len().saturating_sub(1)
+        }
+        fn not(&mut self, inner: usize) -> usize {
+            inner + 1
+        }
+        fn var(&mut self, _: &str) -> usize {
+            0
+        }
+    }
+
+    #[test]
+    fn test_fold() {
+        assert_eq!(Equation::from("a * !b + c * (d + e)".to_string()).fold(&mut Size), 5);
+    }
+
+    #[test]
+    fn test_walk() {
+        let mut nots = 0;
+        Equation::from("!a * !(b + !c)".to_string()).walk(&mut |eq| {
+            if let Equation::Not(_) = *eq {
+                nots += 1
+            }
+        });
+        assert_eq!(nots, 3);
+    }
+
+    #[test]
+    fn test_rewrite() {
+        // removes the double negations
+        let eq = Equation::from("!!a * !!!b".to_string()).rewrite(&mut |eq| match eq {
+            Equation::Not(_) => match eq.inners()[0] {
+                &Equation::Not(_) => eq.inners()[0].inners()[0].clone(),
+                _ => eq,
+            },
+            e => e,
+        });
+        assert_eq!(format!("{}", eq), "((a * ! b))");
+        let renamed = eq.map(&mut |eq| match eq {
+            Equation::Var(v) => Equation::Var(v.to_uppercase()),
+            e => e,
+        });
+        assert_eq!(format!("{}", renamed), "((A * ! B))");
+    }
+}
@@ -0,0 +1,455 @@
+//! Generates synthesizable Verilog and VHDL from named equations.
+
+use equation::Equation;
+use std::collections::{HashMap, HashSet};
+
+/// Options of the generated module.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HdlOptions {
+    pub module_name: String,
+    /// Renames the variables and the outputs in the ports of the module.
+    pub port_names: HashMap<String, String>,
+    /// Declares an intermediate wire for every subterm used more than once.
+    pub shared_wires: bool,
+    pub wire_prefix: String,
+}
+
+impl HdlOptions {
+    pub fn new(module_name: &str) -> Self {
+        HdlOptions {
+            module_name: module_name.to_string(),
+            port_names: HashMap::new(),
+            shared_wires: false,
+            wire_prefix: "w".to_string(),
+        }
+    }
+
+    fn port(&self, name: &str) -> String {
+        self.port_names
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| name.to_string())
+    }
+}
+
+const VERILOG_KEYWORDS: [&str; 33] = [
+    "always", "and", "assign", "begin", "buf", "case", "default", "else", "end", "endcase",
+    "endfunction", "endmodule", "for", "function", "if", "initial", "inout", "input", "integer",
+    "module", "nand", "nor", "not", "or", "output", "parameter", "reg", "supply0", "supply1",
+    "wire", "wor", "xnor", "xor",
+];
+
+const VHDL_KEYWORDS: [&str; 67] = [
+    "abs", "access", "after", "alias", "all", "and", "architecture", "array", "assert",
+    "attribute", "begin", "block", "body", "buffer", "bus", "case", "component", "configuration",
+    "constant", "downto", "else", "elsif", "end", "entity", "exit", "file", "for", "function",
+    "generate", "generic", "if", "in", "inout", "is", "label", "library", "loop", "map", "mod",
+    "nand", "new", "next", "nor", "not", "null", "of", "on", "open", "or", "others", "out",
+    "package", "port", "process", "range", "record", "rem", "return", "select", "signal", "then",
+    "to", "type", "use", "variable", "when", "xor",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Language {
+    Verilog,
+    Vhdl,
+}
+
+impl Language {
+    fn or(self) -> &'static str {
+        match self {
+            Language::Verilog => " | ",
+            Language::Vhdl => " or ",
+        }
+    }
+
+    fn and(self) -> &'static str {
+        match self {
+            Language::Verilog => " & ",
+            Language::Vhdl => " and ",
+        }
+    }
+
+    fn not(self, inner: String) -> String {
+        match self {
+            Language::Verilog => format!("~{}", inner),
+            Language::Vhdl => format!("not {}", inner),
+        }
+    }
+
+    fn not_parenthesized(self, inner: String) -> String {
+        match self {
+            // not not a is not valid VHDL
+            Language::Vhdl if inner.starts_with("not ") => format!("not ({})", inner),
+            _ => self.not(inner),
+        }
+    }
+
+    /// Returns a valid identifier close to the name, the invalid characters being replaced by
+    /// underscores and the keywords suffixed.
+    fn identifier(self, name: &str) -> String {
+        let mut id = String::new();
+        for c in name.chars() {
+            let c = match c.is_ascii_alphanumeric() {
+                true => c,
+                false => '_',
+            };
+            // VHDL forbids two consecutive underscores
+            if !(c == '_' && self == Language::Vhdl && id.ends_with('_')) {
+                id.push(c);
+            }
+        }
+        if self == Language::Vhdl {
+            id = id.trim_matches('_').to_string();
+        }
+        if !id.starts_with(|c: char| c.is_ascii_alphabetic() || (c == '_' && self == Language::Verilog)) {
+            id = format!("v_{}", id).trim_end_matches('_').to_string();
+        }
+        let keywords: &[&str] = match self {
+            Language::Verilog => &VERILOG_KEYWORDS,
+            Language::Vhdl => &VHDL_KEYWORDS,
+        };
+        match keywords.contains(&id.to_lowercase().as_str()) {
+            true => format!("{}_x", id),
+            false => id,
+        }
+    }
+
+    /// Returns the identifier made unique among the taken ones by a numeric suffix, the
+    /// comparison ignoring the case in VHDL.
+    fn unique(self, id: String, taken: &mut HashSet<String>) -> String {
+        let key = |id: &str| match self {
+            Language::Verilog => id.to_string(),
+            Language::Vhdl => id.to_lowercase(),
+        };
+        let mut unique = id.clone();
+        let mut i = 2;
+        while taken.contains(&key(&unique)) {
+            unique = format!("{}_{}", id, i);
+            i += 1;
+        }
+        taken.insert(key(&unique));
+        unique
+    }
+
+    fn constant(self, value: bool) -> &'static str {
+        match (self, value) {
+            (Language::Verilog, true) => "1'b1",
+            (Language::Verilog, false) => "1'b0",
+            (Language::Vhdl, true) => "'1'",
+            (Language::Vhdl, false) => "'0'",
+        }
+    }
+}
+
+/// True if the equation is neither a variable, a negated variable nor a constant.
+fn is_compound(eq: &Equation) -> bool {
+    match *eq {
+        Equation::Var(_) => false,
+        Equation::Not(_) => is_compound(eq.inners()[0].unwrap_single()),
+        _ => eq.inners().len() > 1,
+    }
+}
+
+/// Counts the occurrences of every compound subterm, listing them children first.
+fn count_subterms(
+    eq: &Equation,
+    counts: &mut HashMap<String, usize>,
+    order: &mut Vec<(String, Equation)>,
+) {
+    let eq = eq.unwrap_single();
+    if !is_compound(eq) {
+        return;
+    }
+    for inner in eq.inners() {
+        count_subterms(inner, counts, order);
+    }
+    let key = format!("{}", eq);
+    let count = counts.entry(key.clone()).or_insert(0);
+    *count += 1;
+    if *count == 1 {
+        order.push((key, eq.clone()));
+    }
+}
+
+struct Emitter {
+    language: Language,
+    /// Variable or output to the identifier of its port.
+    ports: HashMap<String, String>,
+    /// Printed subterm to the name of its wire.
+    wires: HashMap<String, String>,
+    module_name: String,
+}
+
+impl Emitter {
+    fn port(&self, name: &str) -> &str {
+        &self.ports[name]
+    }
+
+    /// Returns the expression, using the wires for the shared subterms.
+    fn expr(&self, eq: &Equation, top: bool) -> String {
+        let eq = eq.unwrap_single();
+        if let Some(wire) = self.wires.get(&format!("{}", eq)) {
+            return wire.clone();
+        }
+        self.driver(eq, top)
+    }
+
+    /// Returns the expression of the node built from its operands, the node itself being never
+    /// replaced by its wire so that a wire is not driven by itself.
+    fn driver(&self, eq: &Equation, top: bool) -> String {
+        let eq = eq.unwrap_single();
+        match *eq {
+            Equation::Var(ref v) => self.port(v).to_string(),
+            Equation::Not(_) => self.language.not_parenthesized(self.expr(eq.inners()[0], false)),
+            _ if top || !is_compound(eq) => self.compound(eq),
+            _ => format!("({})", self.compound(eq)),
+        }
+    }
+
+    /// Returns the operands of a sum or a product joined by the operator.
+    fn compound(&self, eq: &Equation) -> String {
+        let (op, empty) = match *eq {
+            Equation::Sum(_) => (self.language.or(), false),
+            _ => (self.language.and(), true),
+        };
+        match eq.inners().len() {
+            0 => self.language.constant(empty).to_string(),
+            _ => eq
+                .inners()
+                .into_iter()
+                .map(|inner| self.expr(inner, false))
+                .collect::<Vec<String>>()
+                .join(op),
+        }
+    }
+}
+
+/// Returns the sorted inputs of the outputs and the shared subterms with their wire names.
+fn prepare(
+    language: Language,
+    outputs: &[(String, Equation)],
+    options: &HdlOptions,
+) -> (Vec<String>, Vec<(String, Equation)>, Emitter) {
+    let mut inputs = outputs
+        .iter()
+        .flat_map(|(_, eq)| eq.get_owned_vars())
+        .collect::<Vec<String>>();
+    inputs.sort();
+    inputs.dedup();
+    let mut counts = HashMap::new();
+    let mut order = vec![];
+    if options.shared_wires {
+        for (_, eq) in outputs.iter() {
+            count_subterms(eq, &mut counts, &mut order);
+        }
+    }
+    let shared = order
+        .into_iter()
+        .filter(|(key, _)| counts[key] > 1)
+        .enumerate()
+        .map(|(i, (key, eq))| (format!("{}{}", options.wire_prefix, i), (key, eq)))
+        .collect::<Vec<(String, (String, Equation))>>();
+    let mut taken = HashSet::new();
+    let module_name = language.identifier(&options.module_name);
+    let mut emitter = Emitter {
+        language,
+        ports: HashMap::new(),
+        wires: HashMap::new(),
+        module_name,
+    };
+    let names = inputs.iter().chain(outputs.iter().map(|(o, _)| o));
+    for name in names {
+        if !emitter.ports.contains_key(name) {
+            let id = language.unique(language.identifier(&options.port(name)), &mut taken);
+            emitter.ports.insert(name.clone(), id);
+        }
+    }
+    let mut wires = vec![];
+    for (name, (key, eq)) in shared {
+        let name = language.unique(language.identifier(&name), &mut taken);
+        wires.push((name.clone(), eq));
+        emitter.wires.insert(key, name);
+    }
+    (inputs, wires, emitter)
+}
+
+/// Returns a Verilog module with one continuous assignment per output.
+pub fn to_verilog(outputs: &[(String, Equation)], options: &HdlOptions) -> String {
+    let (inputs, wires, emitter) = prepare(Language::Verilog, outputs, options);
+    let ports = inputs
+        .iter()
+        .map(|i| format!("    input wire {}", emitter.port(i)))
+        .chain(
+            outputs
+                .iter()
+                .map(|(o, _)| format!("    output wire {}", emitter.port(o))),
+        )
+        .collect::<Vec<String>>();
+    let mut text = format!("module {} (\n{}\n);\n", emitter.module_name, ports.join(",\n"));
+    for (name, _) in wires.iter() {
+        text.push_str(&format!("    wire {};\n", name));
+    }
+    for (name, eq) in wires.iter() {
+        text.push_str(&format!("    assign {} = {};\n", name, emitter.driver(eq, true)));
+    }
+    for (name, eq) in outputs.iter() {
+        text.push_str(&format!(
+            "    assign {} = {};\n",
+            emitter.port(name),
+            emitter.expr(eq, true)
+        ));
+    }
+    text.push_str("endmodule\n");
+    text
+}
+
+/// Returns a VHDL entity and its architecture with one concurrent assignment per output.
+pub fn to_vhdl(outputs: &[(String, Equation)], options: &HdlOptions) -> String {
+    let (inputs, wires, emitter) = prepare(Language::Vhdl, outputs, options);
+    let name = &emitter.module_name;
+    let ports = inputs
+        .iter()
+        .map(|i| format!("        {} : in std_logic", emitter.port(i)))
+        .chain(
+            outputs
+                .iter()
+                .map(|(o, _)| format!("        {} : out std_logic", emitter.port(o))),
+        )
+        .collect::<Vec<String>>();
+    let mut text = "library ieee;\nuse ieee.std_logic_1164.all;\n\n".to_string();
+    text.push_str(&format!(
+        "entity {} is\n    port (\n{}\n    );\nend entity {};\n\n",
+        name,
+        ports.join(";\n"),
+        name
+    ));
+    text.push_str(&format!("architecture rtl of {} is\n", name));
+    for (wire, _) in wires.iter() {
+        text.push_str(&format!("    signal {} : std_logic;\n", wire));
+    }
+    text.push_str("begin\n");
+    for (wire, eq) in wires.iter() {
+        text.push_str(&format!("    {} <= {};\n", wire, emitter.driver(eq, true)));
+    }
+    for (out, eq) in outputs.iter() {
+        text.push_str(&format!(
+            "    {} <= {};\n",
+            emitter.port(out),
+            emitter.expr(eq, true)
+        ));
+    }
+    text.push_str("end architecture rtl;\n");
+    text
+}
+
+#[cfg(test)]
+mod tests_verilog {
+    use super::*;
+
+    #[test]
+    fn test_basics() {
+        let outputs = vec![(
+            "y".to_string(),
+            Equation::from("a * !b + !(c + a)".to_string()),
+        )];
+        assert_eq!(
+            to_verilog(&outputs, &HdlOptions::new("simple")),
+            "module simple (\n    input wire a,\n    input wire b,\n    input wire c,\n    \
+             output wire y\n);\n    assign y = (a & ~b) | ~(c | a);\nendmodule\n"
+        );
+    }
+
+    #[test]
+    fn test_shared_wires() {
+        let outputs = vec![
+            ("y".to_string(), Equation::from("a * b + c".to_string())),
+            ("z".to_string(), Equation::from("!(a * b) * c".to_string())),
+        ];
+        let mut options = HdlOptions::new("shared");
+        options.shared_wires = true;
+        options.port_names.insert("z".to_string(), "z_o".to_string());
+        assert_eq!(
+            to_verilog(&outputs, &options),
+            "module shared (\n    input wire a,\n    input wire b,\n    input wire c,\n    \
+             output wire y,\n    output wire z_o\n);\n    wire w0;\n    assign w0 = a & b;\n    \
+             assign y = w0 | c;\n    assign z_o = ~w0 & c;\nendmodule\n"
+        );
+    }
+
+    #[test]
+    fn test_shared_negation() {
+        let outputs = vec![
+            ("y".to_string(), Equation::from("!(a * b) + c".to_string())),
+            ("z".to_string(), Equation::from("!(a * b) * d".to_string())),
+        ];
+        let mut options = HdlOptions::new("shared");
+        options.shared_wires = true;
+        let verilog = to_verilog(&outputs, &options);
+        assert!(verilog.contains(
+            "    assign w0 = a & b;\n    assign w1 = ~w0;\n    assign y = w1 | c;\n    \
+             assign z = w1 & d;\n"
+        ));
+        let vhdl = to_vhdl(&outputs, &options);
+        assert!(vhdl.contains("    w0 <= a and b;\n    w1 <= not w0;\n    y <= w1 or c;\n"));
+    }
+
+    #[test]
+    fn test_identifiers() {
+        let outputs = vec![
+            ("output".to_string(), Equation::from("bus[3] * a_b + c".to_string())),
+            ("w0".to_string(), Equation::from("(a_b + c) * (a_b + c) * d".to_string())),
+        ];
+        let mut options = HdlOptions::new("my-module");
+        options.shared_wires = true;
+        options.port_names.insert("c".to_string(), "a b".to_string());
+        let verilog = to_verilog(&outputs, &options);
+        assert!(verilog.starts_with(
+            "module my_module (\n    input wire a_b,\n    input wire bus_3_,\n    input wire a_b_2,\n    \
+             input wire d,\n    output wire output_x,\n    output wire w0\n);\n    wire w0_2;\n"
+        ));
+        assert!(verilog.contains("    assign output_x = (bus_3_ & a_b) | a_b_2;\n"));
+        assert!(verilog.contains("    assign w0_2 = a_b | a_b_2;\n"));
+        assert!(verilog.contains("    assign w0 = w0_2 & w0_2 & d;\n"));
+    }
+}
+
+#[cfg(test)]
+mod tests_vhdl {
+    use super::*;
+
+    #[test]
+    fn test_basics() {
+        let outputs = vec![
+            ("y".to_string(), Equation::from("a * !b + c".to_string())),
+            ("t".to_string(), Equation::from("a + !a".to_string()).complete_simplify()),
+        ];
+        let mut options = HdlOptions::new("simple");
+        options.port_names.insert("a".to_string(), "a_i".to_string());
+        assert_eq!(
+            to_vhdl(&outputs, &options),
+            "library ieee;\nuse ieee.std_logic_1164.all;\n\nentity simple is\n    port (\n        \
+             a_i : in std_logic;\n        b : in std_logic;\n        c : in std_logic;\n        \
+             y : out std_logic;\n        t : out std_logic\n    );\nend entity simple;\n\n\
+             architecture rtl of simple is\nbegin\n    y <= (a_i and not b) or c;\n    \
+             t <= '1';\nend architecture rtl;\n"
+        );
+    }
+
+    #[test]
+    fn test_double_negation() {
+        let outputs = vec![("y".to_string(), Equation::from("!!a * !!!b".to_string()))];
+        let vhdl = to_vhdl(&outputs, &HdlOptions::new("neg"));
+        assert!(vhdl.contains("    y <= not (not a) and not (not (not b));\n"));
+    }
+
+    #[test]
+    fn test_identifiers() {
+        let outputs = vec![("out".to_string(), Equation::from("bus[3] * _x + In".to_string()))];
+        let vhdl = to_vhdl(&outputs, &HdlOptions::new("2bits"));
+        assert!(vhdl.contains("entity v_2bits is\n"));
+        assert!(vhdl.contains("        In_x : in std_logic;\n        x : in std_logic;\n        \
+                               bus_3 : in std_logic;\n        out_x : out std_logic\n"));
+        assert!(vhdl.contains("    out_x <= (bus_3 and x) or In_x;\n"));
+    }
+}
@@ -0,0 +1,317 @@
+//! Karnaugh maps of equations of at most 6 variables, with the implicants chosen by mccluskey.
+
+use compiled::Compiled;
+use equation::Equation;
+use mccluskey::{mccluskey_implicants, minterms, PrimeImplicant};
+use printer::Style;
+
+const MAX_VARS: usize = 6;
+const COLORS: [&str; 6] = ["#e41a1c", "#377eb8", "#4daf4a", "#984ea3", "#ff7f00", "#a65628"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KarnaughMap {
+    vars: Vec<String>,
+    /// The first variables index the rows, the others the columns.
+    nb_row_vars: usize,
+    /// Values of the cells, rows and columns being in Gray code order.
+    values: Vec<Vec<bool>>,
+    groups: Vec<PrimeImplicant>,
+}
+
+/// Returns the bits of the Gray code of i, the most significant first.
+fn gray(i: usize, bits: usize) -> Vec<bool> {
+    let code = i ^ (i >> 1);
+    (0..bits).rev().map(|b| (code >> b) & 1 == 1).collect()
+}
+
+fn bits_text(bits: &[bool]) -> String {
+    bits.iter().map(|&b| if b { '1' } else { '0' }).collect()
+}
+
+/// Names the groups A to Z then a to z.
+fn group_name(i: usize) -> char {
+    match i {
+        0..=25 => (b'A' + i as u8) as char,
+        _ => (b'a' + (i - 26) as u8) as char,
+    }
+}
+
+/// Returns the prime implicants chosen by mccluskey to cover the minterms of the equation, in the
+/// order of the variables of get_vars.
+fn implicants(eq: &Equation) -> Result<Vec<PrimeImplicant>, String> {
+    let (_, terms) = minterms(eq)?;
+    Ok(mccluskey_implicants(terms))
+}
+
+impl KarnaughMap {
+    pub fn new(eq: &Equation) -> Result<Self, String> {
+        let compiled = Compiled::new(eq);
+        let vars = compiled.vars().clone();
+        if vars.len() > MAX_VARS {
+            return Err(format!(
+                "karnaugh maps are limited to {} variables, found {}",
+                MAX_VARS,
+                vars.len()
+            ));
+        }
+        let nb_row_vars = vars.len() / 2;
+        let mut map = KarnaughMap {
+            vars,
+            nb_row_vars,
+            values: vec![],
+            groups: vec![],
+        };
+        map.groups = implicants(eq)?;
+        let table = compiled.truth_table()?;
+        map.values = (0..map.nb_rows())
+            .map(|row| {
+                (0..map.nb_cols())
+                    .map(|col| {
+                        // the variable i takes the bit i of the number of the assignment
+                        let assignment = map
+                            .assignment(row, col)
+                            .iter()
+                            .enumerate()
+                            .map(|(i, &v)| (v as usize) << i)
+                            .sum::<usize>();
+                        (table[assignment / 64] >> (assignment % 64)) & 1 == 1
+                    })
+                    .collect()
+            })
+            .collect();
+        Ok(map)
+    }
+
+    pub fn vars(&self) -> &Vec<String> {
+        &self.vars
+    }
+
+    /// Returns the implicants of the simplified sum of products.
+    pub fn groups(&self) -> &Vec<PrimeImplicant> {
+        &self.groups
+    }
+
+    pub fn nb_rows(&self) -> usize {
+        1 << self.nb_row_vars
+    }
+
+    pub fn nb_cols(&self) -> usize {
+        1 << (self.vars.len() - self.nb_row_vars)
+    }
+
+    pub fn value(&self, row: usize, col: usize) -> bool {
+        self.values[row][col]
+    }
+
+    /// Returns the values of the variables in the cell.
+    pub fn assignment(&self, row: usize, col: usize) -> Vec<bool> {
+        let mut values = gray(row, self.nb_row_vars);
+        values.append(&mut gray(col, self.vars.len() - self.nb_row_vars));
+        values
+    }
+
+    /// Returns the names of the groups covering the cell.
+    fn cell_groups(&self, row: usize, col: usize) -> String {
+        let assignment = self.assignment(row, col);
+        self.groups
+            .iter()
+            .enumerate()
+            .filter(|(_, g)| g.covers(&assignment))
+            .map(|(i, _)| group_name(i))
+            .collect()
+    }
+
+    /// Returns the names of the variables of the rows and of the columns.
+    fn axis_names(&self) -> (String, String) {
+        let sep = match self.vars.iter().all(|v| v.chars().count() == 1) {
+            true => "",
+            false => ",",
+        };
+        let (rows, cols) = self.vars.split_at(self.nb_row_vars);
+        (rows.join(sep), cols.join(sep))
+    }
+
+    /// Returns the equation of every group, eg. A = a * !b.
+    fn legend(&self) -> Vec<String> {
+        let vars = self.vars.iter().collect();
+        self.groups
+            .iter()
+            .enumerate()
+            .map(|(i, g)| format!("{} = {}", group_name(i), g.clone().to_eq(&vars).pretty(Style::Arithmetic)))
+            .collect()
+    }
+
+    /// Returns the map as text, every cell showing its value followed by the groups covering it.
+    pub fn to_text(&self) -> String {
+        let (row_names, col_names) = self.axis_names();
+        let corner = format!("{}\\{}", row_names, col_names);
+        let cells = (0..self.nb_rows())
+            .map(|row| {
+                (0..self.nb_cols())
+                    .map(|col| format!("{} {}", self.value(row, col) as u8, self.cell_groups(row, col)))
+                    .map(|cell| cell.trim_end().to_string())
+                    .collect::<Vec<String>>()
+            })
+            .collect::<Vec<Vec<String>>>();
+        let nb_col_vars = self.vars.len() - self.nb_row_vars;
+        let width = cells
+            .iter()
+            .flatten()
+            .map(|c| c.len())
+            .chain(Some(nb_col_vars))
+            .max()
+            .unwrap()
+            + 2;
+        let first = corner.len().max(self.nb_row_vars) + 2;
+        let mut text = format!("{:<w$}", corner, w = first);
+        for col in 0..self.nb_cols() {
+            text.push_str(&format!("{:<w$}", bits_text(&gray(col, nb_col_vars)), w = width));
+        }
+        text = text.trim_end().to_string() + "\n";
+        for (row, line) in cells.iter().enumerate() {
+            let mut printed = format!("{:<w$}", bits_text(&gray(row, self.nb_row_vars)), w = first);
+            for cell in line {
+                printed.push_str(&format!("{:<w$}", cell, w = width));
+            }
+            text.push_str(printed.trim_end());
+            text.push('\n');
+        }
+        if !self.groups.is_empty() {
+            text.push('\n');
+            for line in self.legend() {
+                text.push_str(&line);
+                text.push('\n');
+            }
+        }
+        text
+    }
+
+    /// Returns the map as an SVG image, every group drawing a colored frame in its cells.
+    pub fn to_svg(&self) -> String {
+        let (size, margin) = (40, 50);
+        let width = margin + size * self.nb_cols() + 10;
+        let height = margin + size * self.nb_rows() + 20 * (self.groups.len() + 1);
+        let (row_names, col_names) = self.axis_names();
+        let nb_col_vars = self.vars.len() - self.nb_row_vars;
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" font-family=\"monospace\">\n",
+            width, height
+        );
+        svg.push_str(&format!("  <text x=\"5\" y=\"15\">{}\\{}</text>\n", row_names, col_names));
+        for col in 0..self.nb_cols() {
+            let x = margin + size * col + size / 2;
+            let bits = bits_text(&gray(col, nb_col_vars));
+            svg.push_str(&format!("  <text x=\"{}\" y=\"40\" text-anchor=\"middle\">{}</text>\n", x, bits));
+        }
+        for row in 0..self.nb_rows() {
+            let y = margin + size * row + size / 2 + 5;
+            let bits = bits_text(&gray(row, self.nb_row_vars));
+            svg.push_str(&format!("  <text x=\"5\" y=\"{}\">{}</text>\n", y, bits));
+            for col in 0..self.nb_cols() {
+                let (x, top) = (margin + size * col, margin + size * row);
+                svg.push_str(&format!(
+                    "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"none\" stroke=\"black\"/>\n",
+                    x, top, size, size
+                ));
+                let assignment = self.assignment(row, col);
+                for (i, group) in self.groups.iter().enumerate() {
+                    if !group.covers(&assignment) {
+                        continue;
+                    }
+                    let inset = 3 + 3 * (i % 4);
+                    svg.push_str(&format!(
+                        "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"4\" fill=\"{}\" \
+                         fill-opacity=\"0.2\" stroke=\"{}\"/>\n",
+                        x + inset,
+                        top + inset,
+                        size - 2 * inset,
+                        size - 2 * inset,
+                        COLORS[i % COLORS.len()],
+                        COLORS[i % COLORS.len()]
+                    ));
+                }
+                svg.push_str(&format!(
+                    "  <text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>\n",
+                    x + size / 2,
+                    top + size / 2 + 5,
+                    self.value(row, col) as u8
+                ));
+            }
+        }
+        for (i, line) in self.legend().iter().enumerate() {
+            let y = margin + size * self.nb_rows() + 20 * (i + 1);
+            svg.push_str(&format!(
+                "  <text x=\"5\" y=\"{}\" fill=\"{}\">{}</text>\n",
+                y,
+                COLORS[i % COLORS.len()],
+                line.replace('&', "&amp;").replace('<', "&lt;")
+            ));
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+#[cfg(test)]
+mod tests_karnaugh {
+    use super::*;
+
+    #[test]
+    fn test_gray() {
+        let codes = (0..4).map(|i| bits_text(&gray(i, 2))).collect::<Vec<String>>();
+        assert_eq!(codes, vec!["00", "01", "11", "10"]);
+    }
+
+    #[test]
+    fn test_text() {
+        let map = KarnaughMap::new(&Equation::from("a * !b + c * d".to_string())).unwrap();
+        assert_eq!(
+            map.to_text(),
+            "ab\\cd  00    01    11    10\n\
+             00     0     0     1 B   0\n\
+             01     0     0     1 B   0\n\
+             11     0     0     1 B   0\n\
+             10     1 A   1 A   1 AB  1 A\n\
+             \n\
+             A = a * !b\n\
+             B = c * d\n"
+        );
+    }
+
+    #[test]
+    fn test_cells() {
+        let eq = Equation::from("a * !b + !a * b * c + d * e * !f".to_string());
+        let map = KarnaughMap::new(&eq).unwrap();
+        assert_eq!((map.nb_rows(), map.nb_cols()), (8, 8));
+        for row in 0..8 {
+            for col in 0..8 {
+                let assignment = map.assignment(row, col);
+                let vars = map.vars().iter().cloned().zip(assignment.iter().cloned()).collect();
+                assert_eq!(map.value(row, col), eq.eval(&vars));
+                let covered = map.groups().iter().any(|g| g.covers(&assignment));
+                assert_eq!(covered, map.value(row, col));
+            }
+        }
+        assert!(KarnaughMap::new(&Equation::from("a*b*c*d*e*f*g".to_string())).is_err());
+    }
+
+    #[test]
+    fn test_groups() {
+        let strings = |map: &KarnaughMap| map.groups().iter().map(|g| g.get_string()).collect::<Vec<String>>();
+        let map = KarnaughMap::new(&Equation::from("a * !a * b".to_string())).unwrap();
+        assert!(map.groups().is_empty());
+        assert!(!map.to_text().contains(" = "));
+        // a * !b * c is not a prime, a * c is
+        let map = KarnaughMap::new(&Equation::from("a * b + a * !b * c".to_string())).unwrap();
+        assert_eq!(strings(&map), vec!["11x", "1x1"]);
+        assert!(map.to_text().ends_with("A = a * b\nB = a * c\n"));
+    }
+
+    #[test]
+    fn test_svg() {
+        let svg = KarnaughMap::new(&Equation::from("a * !b".to_string())).unwrap().to_svg();
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert_eq!(svg.matches("fill-opacity").count(), 1);
+        assert!(svg.contains(">A = a * !b</text>"));
+    }
+}
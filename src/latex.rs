@@ -0,0 +1,96 @@
+//! LaTeX tables for the truth table of an equation and the tabulation of the mccluskey algorithm.
+
+use compiled::Compiled;
+use equation::Equation;
+use mccluskey::{PrimeImplicant, Present, Tabulation};
+use printer::{latex_var, Style};
+
+/// Returns the truth table as a tabular, the first variable being the most significant bit.
+pub fn truth_table(eq: &Equation) -> Result<String, String> {
+    let compiled = Compiled::new(eq);
+    let table = compiled.truth_table()?;
+    let vars = compiled.vars();
+    let nb_vars = vars.len();
+    let mut text = format!("\\begin{{tabular}}{{{}|c}}\n", "c".repeat(nb_vars));
+    let header = vars.iter().map(|v| format!("${}$", latex_var(v))).collect::<Vec<String>>();
+    text.push_str(&format!("{} & $f$ \\\\\n\\hline\n", header.join(" & ")));
+    for row in 0..1usize << nb_vars {
+        // the variable i takes the bit i of the number of the assignment
+        let values = (0..nb_vars).map(|i| (row >> (nb_vars - 1 - i)) & 1).collect::<Vec<usize>>();
+        let assignment = values.iter().enumerate().map(|(i, v)| v << i).sum::<usize>();
+        let value = (table[assignment / 64] >> (assignment % 64)) & 1;
+        let cells = values.iter().map(|v| v.to_string()).collect::<Vec<String>>();
+        text.push_str(&format!("{} & {} \\\\\n", cells.join(" & "), value));
+    }
+    text.push_str("\\end{tabular}\n");
+    Ok(text)
+}
+
+/// Returns the cells of an implicant, - for the variables that can take any value.
+fn implicant_row(prime: &PrimeImplicant) -> String {
+    prime
+        .list()
+        .iter()
+        .map(|p| match *p {
+            Present::Yes => "1",
+            Present::No => "0",
+            Present::Any => "-",
+        })
+        .collect::<Vec<&str>>()
+        .join(" & ")
+}
+
+/// Returns one tabular per round of the mccluskey algorithm starting from the minterms, the
+/// implicants being grouped by number of true variables, followed by the resulting sum.
+pub fn tabulation(eq: &Equation) -> Result<String, String> {
+    let tab = Tabulation::from_eq(eq)?;
+    let header = tab.vars.iter().map(|v| format!("${}$", latex_var(v))).collect::<Vec<String>>();
+    let mut text = String::new();
+    for (step, round) in tab.rounds.iter().enumerate() {
+        text.push_str(&format!("% step {}\n", step));
+        text.push_str(&format!("\\begin{{tabular}}{{c|{}}}\n", "c".repeat(tab.vars.len())));
+        text.push_str(&format!("ones & {} \\\\\n", header.join(" & ")));
+        let mut primes = round.groups.concat();
+        primes.sort_by_key(|p| (p.nb_yes(), p.clone()));
+        for (i, prime) in primes.iter().enumerate() {
+            if i == 0 || primes[i - 1].nb_yes() != prime.nb_yes() {
+                text.push_str("\\hline\n");
+            }
+            text.push_str(&format!("{} & {} \\\\\n", prime.nb_yes(), implicant_row(prime)));
+        }
+        text.push_str("\\end{tabular}\n");
+    }
+    let vars = tab.vars.iter().collect();
+    let result = Equation::any(tab.selection.into_iter().map(|p| p.to_eq(&vars)));
+    text.push_str(&format!("\n$f = {}$\n", result.pretty(Style::Latex)));
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests_latex {
+    use super::*;
+
+    #[test]
+    fn test_truth_table() {
+        let table = truth_table(&Equation::from("a * !b".to_string())).unwrap();
+        assert_eq!(
+            table,
+            "\\begin{tabular}{cc|c}\n$a$ & $b$ & $f$ \\\\\n\\hline\n\
+             0 & 0 & 0 \\\\\n0 & 1 & 0 \\\\\n1 & 0 & 1 \\\\\n1 & 1 & 0 \\\\\n\\end{tabular}\n"
+        );
+    }
+
+    #[test]
+    fn test_tabulation() {
+        let text = tabulation(&Equation::from("a*b*c + a*b*!c + a*!b*c".to_string())).unwrap();
+        assert!(text.starts_with("% step 0\n\\begin{tabular}{c|ccc}\nones & $a$ & $b$ & $c$ \\\\\n"));
+        assert!(text.contains("3 & 1 & 1 & 1 \\\\\n"));
+        assert!(text.contains("2 & 1 & - & 1 \\\\\n"));
+        assert!(text.contains("2 & 1 & 1 & - \\\\\n"));
+        // the headers of the two steps, the three minterms and the two primes
+        assert_eq!(text.matches(" \\\\\n").count(), 2 + 3 + 2);
+        assert!(text.contains("% step 1\n"));
+        assert!(!text.contains("% step 2\n"));
+        assert!(text.ends_with("\n$f = ab + ac$\n"));
+    }
+}
@@ -8,19 +8,42 @@ pub enum Token {
     RParen,
     And,
     Or,
+    Xor,
+    Xnor,
+    /// Postfix negation, as in a'.
+    Prime,
+    Assign,
+    Equal,
+    Semicolon,
     Ident(String),
 }
 
+/// The syntax accepted by the lexer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Dialect {
+    /// The default syntax. The dot stays a product as in the first versions, so a dotted name
+    /// such as ctrl.valid is read as ctrl * valid, only the Verilog dialect reading it as a name.
+    Standard,
+    /// Verilog expressions and assign statements, the names being hierarchical (ctrl.valid).
+    Verilog,
+}
+
 pub struct EqLexer {
     text: Vec<char>,
     pos: usize,
+    dialect: Dialect,
 }
 
 impl EqLexer {
     pub fn new(eq: String) -> Self {
+        EqLexer::with_dialect(eq, Dialect::Standard)
+    }
+
+    pub fn with_dialect(eq: String, dialect: Dialect) -> Self {
         EqLexer {
             text: eq.chars().collect(),
             pos: 0,
+            dialect,
         }
     }
 
@@ -55,6 +78,9 @@ impl EqLexer {
     }
 
     pub fn next_token(&mut self) -> Result<Token, String> {
+        if self.dialect == Dialect::Verilog {
+            return self.next_verilog_token();
+        }
         match self.advance() {
             '(' => Ok(Token::LParen),
             ')' => Ok(Token::RParen),
@@ -62,9 +88,15 @@ impl EqLexer {
             '+' => Ok(Token::Or),
             '*' => Ok(Token::And),
             '!' => Ok(Token::Not),
-            '&' => Ok(Token::And),
-            '|' => Ok(Token::Or),
+            '~' => Ok(Token::Not),
+            '&' => Ok(self.doubled('&', Token::And)),
+            '|' => Ok(self.doubled('|', Token::Or)),
             '/' => Ok(Token::Not),
+            '\'' => Ok(Token::Prime),
+            '¬' => Ok(Token::Not),
+            '∧' | '·' => Ok(Token::And),
+            '∨' => Ok(Token::Or),
+            '⊕' => Ok(Token::Xor),
             ' ' => Ok(Token::Ignore),
             '\r' => Ok(Token::Ignore),
             '\t' => Ok(Token::Ignore),
@@ -74,17 +106,51 @@ impl EqLexer {
             '2'...'9' => self.identifier(),
             'a'...'z' => self.identifier(),
             'A'...'Z' => self.identifier(),
+            '_' => self.identifier(),
             c => Err(format!("unexpected token : {}", c)),
         }
     }
 
+    /// Consumes the second char of the C operators && and ||.
+    fn doubled(&mut self, c: char, token: Token) -> Token {
+        if self.peek() == c {
+            self.advance();
+        }
+        token
+    }
+
+    /// Consumes an index such as [3] after a name, the part selects such as [3:0] being refused.
+    fn bit_select(&mut self) -> Result<(), String> {
+        if self.peek() != '[' {
+            return Ok(());
+        }
+        self.advance();
+        let start = self.pos;
+        while self.peek().is_ascii_digit() {
+            self.advance();
+        }
+        match self.peek() {
+            ']' if self.pos > start => {
+                self.advance();
+                Ok(())
+            }
+            _ if self.is_at_end() => Err("unclosed bit select".to_string()),
+            c => Err(format!("only single bit selects such as [3] are supported, found : {}", c)),
+        }
+    }
+
     /// If it is a known keyword, register it as a keyword.
+    /// Names may contain underscores and end with an index (bus[3]), a dot being a product.
     fn identifier(&mut self) -> Result<Token, String> {
         let start = self.pos - 1;
-        while self.peek().is_alphanumeric() && !self.is_at_end() {
+        while self.peek().is_alphanumeric() || self.peek() == '_' {
             self.advance();
         }
-        let sub_string: String = self.text[start..self.pos].into_iter().collect();
+        self.bit_select()?;
+        let sub_string: String = self.text[start..self.pos]
+            .iter()
+            .filter(|c| !c.is_whitespace())
+            .collect();
         match sub_string.as_ref() {
             "not" => Ok(Token::Not),
             "and" => Ok(Token::And),
@@ -92,6 +158,92 @@ impl EqLexer {
             a => Ok(Token::Ident(a.to_string())),
         }
     }
+
+    fn next_verilog_token(&mut self) -> Result<Token, String> {
+        match self.advance() {
+            '(' => Ok(Token::LParen),
+            ')' => Ok(Token::RParen),
+            '&' => Ok(Token::And),
+            '|' => Ok(Token::Or),
+            '!' => Ok(Token::Not),
+            '=' => Ok(Token::Equal),
+            ';' => Ok(Token::Semicolon),
+            '~' if self.peek() == '^' => {
+                self.advance();
+                Ok(Token::Xnor)
+            }
+            '^' if self.peek() == '~' => {
+                self.advance();
+                Ok(Token::Xnor)
+            }
+            '~' => Ok(Token::Not),
+            '^' => Ok(Token::Xor),
+            '/' if self.peek() == '/' => {
+                while self.peek() != '\n' && !self.is_at_end() {
+                    self.advance();
+                }
+                Ok(Token::Ignore)
+            }
+            ' ' | '\r' | '\t' | '\n' => Ok(Token::Ignore),
+            '0'..='9' => self.verilog_number(),
+            'a'..='z' | 'A'..='Z' | '_' => self.verilog_identifier(),
+            c => Err(format!("unexpected token : {}", c)),
+        }
+    }
+
+    /// Reads a one bit constant such as 1, 0, 1'b1 or 1'h0, the width being 1 when given.
+    fn verilog_number(&mut self) -> Result<Token, String> {
+        let start = self.pos - 1;
+        while self.peek().is_ascii_alphanumeric() || self.peek() == '\'' || self.peek() == '_' {
+            self.advance();
+        }
+        let number: String = self.text[start..self.pos].iter().collect();
+        let digits = number.replace('_', "");
+        let (width, base, value) = match digits.find('\'') {
+            None => (None, 'd', digits.as_str()),
+            Some(i) => (
+                Some(&digits[..i]),
+                digits[i + 1..].chars().next().unwrap_or(' ').to_ascii_lowercase(),
+                digits.get(i + 2..).unwrap_or(""),
+            ),
+        };
+        let radix = match base {
+            'b' => 2,
+            'o' => 8,
+            'd' => 10,
+            'h' => 16,
+            _ => return Err(format!("invalid base in the constant : {}", number)),
+        };
+        let one_bit = width.is_none_or(|w| w.parse::<u32>() == Ok(1));
+        match u64::from_str_radix(value, radix) {
+            Ok(0) if one_bit => Ok(Token::False),
+            Ok(1) if one_bit => Ok(Token::True),
+            _ => Err(format!("only one bit constants are supported : {}", number)),
+        }
+    }
+
+    /// Reads a hierarchical identifier with an optional bit select, as in ctrl.bus[3].
+    fn verilog_identifier(&mut self) -> Result<Token, String> {
+        let start = self.pos - 1;
+        loop {
+            let next = self.text.get(self.pos + 1).cloned().unwrap_or('\0');
+            match self.peek() {
+                c if c.is_alphanumeric() || c == '_' || c == '$' => (),
+                '.' if next.is_alphabetic() || next == '_' => (),
+                _ => break,
+            }
+            self.advance();
+        }
+        self.bit_select()?;
+        let sub_string: String = self.text[start..self.pos]
+            .iter()
+            .filter(|c| !c.is_whitespace())
+            .collect();
+        match sub_string.as_ref() {
+            "assign" => Ok(Token::Assign),
+            a => Ok(Token::Ident(a.to_string())),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -134,3 +286,121 @@ mod tests_lexer {
         );
     }
 }
+
+#[cfg(test)]
+mod tests_alternative_lexer {
+    use super::*;
+
+    fn tokens(text: &str) -> Vec<Token> {
+        EqLexer::new(text.to_string()).get_tokens().unwrap()
+    }
+
+    fn ident(name: &str) -> Token {
+        Token::Ident(name.to_string())
+    }
+
+    #[test]
+    fn test_symbols() {
+        assert_eq!(
+            tokens("¬a ∧ (b ∨ c) ⊕ d"),
+            vec![Token::Not, ident("a"), Token::And, Token::LParen, ident("b"), Token::Or,
+                 ident("c"), Token::RParen, Token::Xor, ident("d")]
+        );
+        assert_eq!(
+            tokens("~a && b || c"),
+            vec![Token::Not, ident("a"), Token::And, ident("b"), Token::Or, ident("c")]
+        );
+        assert_eq!(
+            tokens("a' * (b + c)'"),
+            vec![ident("a"), Token::Prime, Token::And, Token::LParen, ident("b"), Token::Or,
+                 ident("c"), Token::RParen, Token::Prime]
+        );
+    }
+
+    #[test]
+    fn test_names() {
+        assert_eq!(
+            tokens("x_1 * bus[3] + _reset_n./b"),
+            vec![ident("x_1"), Token::And, ident("bus[3]"), Token::Or, ident("_reset_n"), Token::And,
+                 Token::Not, ident("b")]
+        );
+        assert!(EqLexer::new("bus[3".to_string()).get_tokens().is_err());
+    }
+
+    #[test]
+    fn test_dotted_names_are_products() {
+        // unlike the Verilog dialect, the standard one keeps the dot as a product
+        assert_eq!(tokens("a.b"), vec![ident("a"), Token::And, ident("b")]);
+        assert_eq!(tokens("ctrl.valid"), vec![ident("ctrl"), Token::And, ident("valid")]);
+        assert_eq!(
+            EqLexer::with_dialect("ctrl.valid".to_string(), Dialect::Verilog).get_tokens(),
+            Ok(vec![ident("ctrl.valid")])
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests_verilog_lexer {
+    use super::*;
+
+    #[test]
+    fn test_basics() {
+        assert_eq!(
+            EqLexer::with_dialect("assign y = ~a[3] ^ (b_1 ~^ 1'b0); // out".to_string(), Dialect::Verilog)
+                .get_tokens(),
+            Ok(vec![
+                Token::Assign,
+                Token::Ident("y".to_string()),
+                Token::Equal,
+                Token::Not,
+                Token::Ident("a[3]".to_string()),
+                Token::Xor,
+                Token::LParen,
+                Token::Ident("b_1".to_string()),
+                Token::Xnor,
+                Token::False,
+                Token::RParen,
+                Token::Semicolon,
+            ])
+        );
+        assert!(EqLexer::with_dialect("a + b".to_string(), Dialect::Verilog).get_tokens().is_err());
+        assert!(EqLexer::with_dialect("4'b1010".to_string(), Dialect::Verilog).get_tokens().is_err());
+    }
+
+    #[test]
+    fn test_hierarchical_names() {
+        assert_eq!(
+            EqLexer::with_dialect("top.ctrl.valid & u_1.bus[3]".to_string(), Dialect::Verilog).get_tokens(),
+            Ok(vec![
+                Token::Ident("top.ctrl.valid".to_string()),
+                Token::And,
+                Token::Ident("u_1.bus[3]".to_string()),
+            ])
+        );
+        assert!(EqLexer::with_dialect("a. b".to_string(), Dialect::Verilog).get_tokens().is_err());
+    }
+
+    #[test]
+    fn test_numbers() {
+        let number = |text: &str| EqLexer::with_dialect(text.to_string(), Dialect::Verilog).get_tokens();
+        assert_eq!(number("1'b1"), Ok(vec![Token::True]));
+        assert_eq!(number("1'H0"), Ok(vec![Token::False]));
+        assert_eq!(number("01'd1"), Ok(vec![Token::True]));
+        assert_eq!(number("0"), Ok(vec![Token::False]));
+        assert!(number("4'b0001").is_err());
+        assert!(number("2'b01").is_err());
+        assert!(number("1'b2").is_err());
+        assert!(number("1'x1").is_err());
+        assert!(number("1'b").is_err());
+        assert!(number("2").is_err());
+    }
+
+    #[test]
+    fn test_part_select() {
+        let tokens = |text: &str| EqLexer::with_dialect(text.to_string(), Dialect::Verilog).get_tokens();
+        assert!(tokens("a[3:0]").is_err());
+        assert!(tokens("a[]").is_err());
+        assert!(tokens("a[i]").is_err());
+        assert_eq!(tokens("a[12]"), Ok(vec![Token::Ident("a[12]".to_string())]));
+    }
+}
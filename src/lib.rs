@@ -0,0 +1,29 @@
+//! Simplification of boolean equations, with their conversions to and from other formats.
+
+extern crate rand;
+extern crate num_bigint;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
+
+#[macro_use]
+pub mod equation;
+pub mod lexer;
+pub mod parser;
+pub mod mccluskey;
+pub mod blif;
+pub mod hdl;
+pub mod codegen;
+pub mod compiled;
+pub mod bdd;
+pub mod fold;
+pub mod printer;
+pub mod latex;
+pub mod dot;
+pub mod karnaugh;
+pub mod trace;
+pub mod simplify;
+#[cfg(feature = "serde")]
+pub mod serialize;
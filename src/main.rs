@@ -1,18 +1,75 @@
-#![feature(box_patterns)]
-mod equation;
-mod lexer;
-mod parser;
+#[cfg_attr(test, macro_use)]
+extern crate booleansimplify;
+use booleansimplify::equation::Equation;
+use booleansimplify::printer::Style;
+use booleansimplify::simplify::SimplifyOptions;
 use std::env;
-extern crate rand;
-use equation::Equation;
-mod mccluskey;
+use std::process;
+
+/// Simplifies the equation given as argument, the passes and the form being chosen with eg.
+/// --passes nnf,distribution,flatten,espresso,factoring --form multilevel
+fn run(args: &[String]) -> Result<String, String> {
+    let mut options = SimplifyOptions::default();
+    let mut text = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value after {}", arg));
+        match arg.as_str() {
+            "--passes" => options.passes = value()?.split(',').map(|p| p.parse()).collect::<Result<_, _>>()?,
+            "--form" => options.form = value()?.parse()?,
+            "--max-rounds" => options.max_rounds = value()?.parse().map_err(|e| format!("{} : {}", arg, e))?,
+            _ if arg.starts_with("--") => return Err(format!("unknown option : {}", arg)),
+            _ if text.is_some() => return Err(format!("unexpected argument : {}", arg)),
+            _ => text = Some(arg),
+        }
+    }
+    let eq = Equation::parse(text.ok_or("missing equation")?)?;
+    Ok(eq.simplify_with(&options).pretty(Style::Arithmetic))
+}
 
 fn main() {
+    let args = env::args().skip(1).collect::<Vec<String>>();
+    if !args.is_empty() {
+        match run(&args) {
+            Ok(text) => println!("{}", text),
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        }
+        return;
+    }
     // These are the worst
-    let eq = Equation::from("!(a+!b * !(x*z + !(!a*!f + !b*!c + !d*!e)))".to_string().replace("&", ""));
+    let eq = Equation::from("!(a+!b * !(x*z + !(!a*!f + !b*!c + !d*!e)))".to_string());
     println!("eq : {}", eq);
     let new_eq = eq.complete_simplify();
     println!("depth : {}", new_eq.depth(0));
     println!("new eq len : {}", format!("{}", new_eq).len()); // 280 characters.
     println!("new eq len : {}", new_eq);
 }
+
+#[cfg(test)]
+mod tests_cli {
+    use super::*;
+
+    fn args(text: &str) -> Vec<String> {
+        text.split(' ').map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn test_run() {
+        assert_eq!(run(&args("a*b+a*!b+c")), Ok("a + c".to_string()));
+        assert_eq!(run(&args("a*b+a*!b+c")), Ok(bexpr!(a + c).pretty(Style::Arithmetic)));
+        assert_eq!(
+            run(&args("--passes nnf,distribution,flatten,espresso,factoring --form multilevel a*b+a*c+d")),
+            Ok("a * (b + c) + d".to_string())
+        );
+        assert_eq!(run(&args("--form pos a*b+c")), Ok("(a + c) * (b + c)".to_string()));
+        assert!(run(&args("--passes nnf,quine a")).is_err());
+        assert!(run(&args("--form")).is_err());
+        assert!(run(&args("--form sop")).is_err());
+        assert!(run(&["a + b) * c".to_string()]).is_err());
+        assert_eq!(run(&args("--pases nnf a*b")), Err("unknown option : --pases".to_string()));
+        assert_eq!(run(&args("a*b c")), Err("unexpected argument : c".to_string()));
+    }
+}
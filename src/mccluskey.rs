@@ -1,15 +1,43 @@
 /// Applyes the mccluskey algorithm to find the essential prime implicants of an expression.
 ///
 use equation::{Equation, Sum, Prod, Not};
-use std::collections::HashSet;
-#[derive(Debug, PartialOrd, PartialEq, Copy, Clone, Hash, Eq)]
+use compiled::Compiled;
+use printer::Style;
+use std::collections::HashMap;
+#[derive(Debug, PartialOrd, Ord, PartialEq, Copy, Clone, Hash, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum Present {
     No,
     Yes,
     Any,
 }
 
-#[derive(Debug, Clone, PartialOrd, Hash, Eq, PartialEq)]
+/// Present is also the three valued logic of Kleene, Any standing for an unknown value.
+impl Present {
+    pub fn negate(self) -> Self {
+        match self {
+            Present::Yes => Present::No,
+            Present::No => Present::Yes,
+            Present::Any => Present::Any,
+        }
+    }
+
+    pub fn and(self, other : Self) -> Self {
+        match (self, other) {
+            (Present::No, _) | (_, Present::No) => Present::No,
+            (Present::Yes, Present::Yes) => Present::Yes,
+            _ => Present::Any,
+        }
+    }
+
+    pub fn or(self, other : Self) -> Self {
+        self.negate().and(other.negate()).negate()
+    }
+}
+
+/// Implicants are ordered as their strings, 0 < 1 < x.
+#[derive(Debug, Clone, PartialOrd, Ord, Hash, Eq, PartialEq)]
 pub struct PrimeImplicant {
     list : Vec<Present>,
     nb_any : usize,
@@ -17,14 +45,21 @@ pub struct PrimeImplicant {
 }
 
 impl PrimeImplicant {
-    /// Creates a primeImplicant from an equation and the order of the variables.
+    /// Creates a primeImplicant from an equation and the order of the variables, the variables
+    /// missing from the order are ignored.
     pub fn from_eq(eq : &Equation, vars : &Vec<&String>) -> Self {
-        let len = vars.len();
         match eq {
             &Equation::Sum(ref s) => panic!("do not give a sum to from_eq"),
-            &Equation::Not(ref n) => Self::any_with(vars.iter().position(|&x| x == n.get_only_var()).unwrap(), Present::No, len),
+            &Equation::Not(ref n) => Self::from_literal(n.get_only_var(), Present::No, vars),
             &Equation::Prod(ref p) => Self::from_prod(p, vars),
-            &Equation::Var(ref v) => Self::any_with(vars.iter().position(|&x| x == v).unwrap(), Present::Yes, len),
+            &Equation::Var(ref v) => Self::from_literal(v, Present::Yes, vars),
+        }
+    }
+    /// creates a prime from a variable or its negation
+    pub fn from_literal(var : &String, val : Present, vars : &[&String]) -> Self {
+        match vars.iter().position(|&x| x == var) {
+            Some(pos) => Self::any_with(pos, val, vars.len()),
+            None => Self::new(vec![Present::Any; vars.len()]),
         }
     }
     /// creates a prime from a product ( eg. a*!x)
@@ -44,6 +79,22 @@ impl PrimeImplicant {
             nb_yes: nb_yes,
         }
     }
+    /// Creates a prime from the value of every variable.
+    pub fn new(list : Vec<Present>) -> Self {
+        let nb_any = list.iter().filter(|&p|p == &Present::Any).count();
+        let nb_yes = list.iter().filter(|&p|p == &Present::Yes).count();
+        PrimeImplicant {
+            list,
+            nb_any,
+            nb_yes,
+        }
+    }
+
+    /// Returns the value of every variable.
+    pub fn list(&self) -> &Vec<Present> {
+        &self.list
+    }
+
     /// create a prime of the form x or !x
     pub fn any_with(pos : usize, val : Present, len : usize) -> Self {
         PrimeImplicant {
@@ -58,13 +109,17 @@ impl PrimeImplicant {
             },
         }
     }
-    /// Returns to the product expression.
+    /// Returns to the product expression, a single literal being returned as is.
     pub fn to_eq(self, vars : &Vec<&String>) -> Equation {
-        Equation::Prod(Box::new(Prod::new(self.list.into_iter().zip(vars.iter()).filter_map(|(pres, var)| match pres {
+        let mut literals = self.list.into_iter().zip(vars.iter()).filter_map(|(pres, var)| match pres {
             Present::Yes => Some(Equation::Var(var.to_string())),
             Present::No => Some(Equation::Not(Box::new(Not::new(Equation::Var(var.to_string()))))),
             Present::Any => None
-        }).collect()))).simplified()
+        }).collect::<Vec<Equation>>();
+        match literals.len() {
+            1 => literals.pop().unwrap(),
+            _ => Equation::Prod(Box::new(Prod::new(literals))),
+        }
     }
 
     /// Checks that there is only one difference
@@ -84,6 +139,46 @@ impl PrimeImplicant {
         }
     }
 
+    /// Returns the number of variables that can take any value.
+    pub fn nb_any(&self) -> usize {
+        self.nb_any
+    }
+
+    /// True if the assignment, values[i] being the value of the variable i, satisfies the
+    /// implicant.
+    pub fn covers(&self, values : &[bool]) -> bool {
+        self.list.iter().zip(values.iter()).all(|(p, &v)| match *p {
+            Present::Yes => v,
+            Present::No => !v,
+            Present::Any => true,
+        })
+    }
+
+    /// True if every assignment satisfying other satisfies the implicant.
+    pub fn contains(&self, other : &Self) -> bool {
+        self.list.iter().zip(other.list.iter()).all(|(&a, &b)| a == Present::Any || a == b)
+    }
+
+    /// Returns the number of variables that must be true.
+    pub fn nb_yes(&self) -> usize {
+        self.nb_yes
+    }
+
+    /// True if no assignment can satisfy both implicants.
+    pub fn is_disjoint(&self, other : &Self) -> bool {
+        self.list.iter().zip(other.list.iter()).any(|(a, b)| matches!((a, b),
+            (Present::Yes, Present::No) | (Present::No, Present::Yes)))
+    }
+
+    /// Probability of the product being true, probs[i] being the probability of the variable i.
+    pub fn probability(&self, probs : &[f64]) -> f64 {
+        self.list.iter().zip(probs.iter()).map(|(pres, p)| match pres {
+            Present::Yes => *p,
+            Present::No => 1.0 - p,
+            Present::Any => 1.0,
+        }).product()
+    }
+
     /// returns a string in the form 01001xx0 representing the inner list
     pub fn get_string(&self) -> String {
         self.list.iter().map(|v|match v {
@@ -94,100 +189,320 @@ impl PrimeImplicant {
     }
 }
 
-/// Returns an array of arrays of primes implicants sorted by nb_any
-/// the index in the first array is the number of yes in the sub array.
-pub fn sort_prime_implicants(mut impls : Vec<PrimeImplicant>) -> Vec<Vec<PrimeImplicant>>{
-    impls.sort_by_key(|i|i.nb_yes);
-    let mut impls_by_yes = vec![];
-    let mut current_impls = vec![];
-    let mut current_yes = 1;
+/// Returns the implicants grouped by number of yes, the index in the first array being the
+/// number of yes of the implicants of the sub array.
+pub fn sort_prime_implicants(impls : Vec<PrimeImplicant>) -> Vec<Vec<PrimeImplicant>>{
+    let mut impls_by_yes : Vec<Vec<PrimeImplicant>> = vec![];
     for i in impls {
-        if i.nb_yes > current_yes {
-            while i.nb_yes > current_yes+1 {
-                current_yes+=1;
-                impls_by_yes.push(vec![]);
-            }
-            impls_by_yes.push(current_impls);
-            current_impls = vec![i];
-        } else {
-            current_impls.push(i);
+        while impls_by_yes.len() <= i.nb_yes {
+            impls_by_yes.push(vec![]);
         }
+        impls_by_yes[i.nb_yes].push(i);
     }
-    impls_by_yes.push(current_impls);
     impls_by_yes
 }
-/// Take a primeimplicant (eg 0100x1) and a list of primes implicants that have 1 more yes and are
-/// sorted by number of any and compare them.
-/// If merging is needed, remove the prime implicant from the others.
-pub fn merge_similar(prime : PrimeImplicant, others : &mut Vec<PrimeImplicant>)->Option<PrimeImplicant>{
-    for i in 0..others.len() {
-        if prime.can_merge(&others[i]){
-            let to_ret = prime.merge(&others[i]);
-            others.remove(i);
-            return Some(to_ret)
+
+/// A round of the algorithm : every implicant is merged with each of those with one more yes
+/// differing by one variable, the merges being added to merges if given. The implicants
+/// which did not merge are added to primes and the sorted merged ones are returned. Calls check
+/// before every group and returns None as soon as it returns false.
+fn merge_round(
+    groups : &[Vec<PrimeImplicant>],
+    mut merges : Option<&mut Vec<Merge>>,
+    primes : &mut Vec<PrimeImplicant>,
+    check : &mut dyn FnMut() -> bool,
+) -> Option<Vec<PrimeImplicant>> {
+    let mut used = groups.iter().map(|g| vec![false; g.len()]).collect::<Vec<Vec<bool>>>();
+    let mut merged = vec![];
+    for nb_yes in 1..groups.len() {
+        if !check() {
+            return None;
+        }
+        // the implicants differing from a by one variable are a with one No replaced by a Yes
+        let next = groups[nb_yes].iter().enumerate().map(|(j, b)| (b, j)).collect::<HashMap<&PrimeImplicant, usize>>();
+        for (i, a) in groups[nb_yes - 1].iter().enumerate() {
+            for pos in (0..a.list.len()).filter(|&pos| a.list[pos] == Present::No) {
+                let mut list = a.list.clone();
+                list[pos] = Present::Yes;
+                if let Some(&j) = next.get(&PrimeImplicant::new(list)) {
+                    let b = &groups[nb_yes][j];
+                    let m = a.merge(b);
+                    if let Some(ref mut merges) = merges {
+                        merges.push((a.clone(), b.clone(), m.clone()));
+                    }
+                    merged.push(m);
+                    used[nb_yes - 1][i] = true;
+                    used[nb_yes][j] = true;
+                }
+            }
         }
     }
-    None
-}
-/// Given the primes implicants of an expression and the associated variables, returns the sop
-/// containing only essentials primes implicants.
-pub fn mccluskey((vars, mut impls) : (Vec<String>, Vec<PrimeImplicant>)) -> Vec<Equation>{
-    let mut hs = HashSet::new();
-    mccluskey_primes(impls)
-    .into_iter()
-    .map(|var| hs.insert(var))
-    .for_each(drop);
-    let mut to_ret = hs.into_iter().collect::<Vec<PrimeImplicant>>();
-    to_ret.sort_by_key(|p|p.get_string());
-    to_ret.into_iter().map(|p|p.to_eq(&vars.iter().collect())).collect()
+    for (group, used) in groups.iter().zip(used.iter()) {
+        primes.extend(group.iter().zip(used.iter()).filter(|&(_, &u)| !u).map(|(p, _)| p.clone()));
+    }
+    merged.sort();
+    merged.dedup();
+    Some(merged)
 }
 
-/// Checks for merge on implicants differing by one Yes
-pub fn mccluskey_pass_one(mut sorted_primes : Vec<Vec<PrimeImplicant>>) -> Vec<PrimeImplicant> {
-    let mut to_add = vec![];
-    for nb_yes in 0..sorted_primes.len()-1 { // for every list
-        let (arr_now , arr_next) = sorted_primes.split_at_mut(nb_yes+1);
-        let (now, next) = (arr_now.last_mut().unwrap(), arr_next.first_mut().unwrap());
-        for base_id in 0..now.len(){
-            match merge_similar(now[base_id].clone(), next){
-                Some(new) => to_add.push(new),
-                None => to_add.push(now[base_id].clone()),
-            }
+/// Returns every round of the algorithm, starting from the given implicants, the merges being
+/// recorded if logged. Calls check before every round and every group, returning None as soon as
+/// it returns false.
+fn rounds_checked(mut impls : Vec<PrimeImplicant>, logged : bool, check : &mut dyn FnMut() -> bool) -> Option<Vec<Round>> {
+    impls.sort();
+    impls.dedup();
+    let mut rounds = vec![];
+    while !impls.is_empty() {
+        if !check() {
+            return None;
         }
+        let groups = sort_prime_implicants(impls);
+        let (mut merges, mut primes) = (vec![], vec![]);
+        impls = merge_round(&groups, if logged { Some(&mut merges) } else { None }, &mut primes, check)?;
+        primes.sort();
+        rounds.push(Round { groups, merges, primes });
     }
-    to_add.append(&mut sorted_primes.last_mut().unwrap());
-    to_add
+    Some(rounds)
 }
-/// Merge on implicants differing by one Any
-pub fn mccluskey_pass_two(mut sorted_primes : Vec<Vec<PrimeImplicant>>) -> Vec<PrimeImplicant> {
-    let mut to_add = vec![];
-    while let Some(mut now) = sorted_primes.pop(){
-        while let Some(mut curr) = now.pop(){
-            match merge_similar(curr.clone(), &mut now){
-                Some(new) => to_add.push(new),
-                None => to_add.push(curr),
+
+/// Returns the primes that are the only ones to contain one of the terms, and the cover made of
+/// them and, while a term is not covered, of the prime containing the most uncovered terms.
+fn select_cover(terms : &[PrimeImplicant], primes : &[PrimeImplicant]) -> (Vec<PrimeImplicant>, Vec<PrimeImplicant>) {
+    let mut essentials = terms.iter()
+        .filter_map(|t| {
+            let mut containing = primes.iter().filter(|p| p.contains(t));
+            match (containing.next(), containing.next()) {
+                (Some(p), None) => Some(p.clone()),
+                _ => None,
             }
+        })
+        .collect::<Vec<PrimeImplicant>>();
+    essentials.sort();
+    essentials.dedup();
+    let mut selection = essentials.clone();
+    loop {
+        let uncovered = terms.iter()
+            .filter(|t| !selection.iter().any(|p| p.contains(t)))
+            .collect::<Vec<&PrimeImplicant>>();
+        let best = primes.iter()
+            .map(|p| (uncovered.iter().filter(|t| p.contains(t)).count(), p))
+            .filter(|&(n, _)| n > 0)
+            .max_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(a.1)));
+        match best {
+            Some((_, p)) => selection.push(p.clone()),
+            None => break,
         }
     }
-    to_add
+    selection.sort();
+    (essentials, selection)
 }
 
-/// Returns the essentials primes implicants given some non essential primes implicants
-pub fn mccluskey_primes(mut impls : Vec<PrimeImplicant>)-> Vec<PrimeImplicant>{
-    let mut size_before = impls.len();
-    let mut sorted_primes = sort_prime_implicants(impls); // l of l
-    loop {
-        let mut to_add = mccluskey_pass_two(sort_prime_implicants(mccluskey_pass_one(sorted_primes)));
-        if size_before == to_add.len(){
-            return to_add;
+/// Given the primes implicants of an expression and the associated variables, returns the sop
+/// made of the essentials primes implicants and of the primes needed to cover the others terms.
+pub fn mccluskey((vars, impls) : (Vec<String>, Vec<PrimeImplicant>)) -> Vec<Equation>{
+    mccluskey_checked((vars, impls), &mut || true).unwrap()
+}
+
+/// Same as mccluskey, stopped as mccluskey_primes_checked.
+pub fn mccluskey_checked((vars, impls) : (Vec<String>, Vec<PrimeImplicant>), check : &mut dyn FnMut() -> bool) -> Option<Vec<Equation>> {
+    let vars = vars.iter().collect();
+    Some(mccluskey_implicants_checked(impls, check)?.into_iter().map(|p|p.to_eq(&vars)).collect())
+}
+
+/// Returns the sorted implicants of the sop given by mccluskey.
+pub fn mccluskey_implicants(impls : Vec<PrimeImplicant>) -> Vec<PrimeImplicant> {
+    mccluskey_implicants_checked(impls, &mut || true).unwrap()
+}
+
+/// Same as mccluskey_implicants, stopped as mccluskey_primes_checked.
+pub fn mccluskey_implicants_checked(impls : Vec<PrimeImplicant>, check : &mut dyn FnMut() -> bool) -> Option<Vec<PrimeImplicant>> {
+    let rounds = rounds_checked(impls, false, check)?;
+    let primes = round_primes(&rounds);
+    let terms = rounds.first().map_or(vec![], |r| r.groups.concat());
+    Some(select_cover(&terms, &primes).1)
+}
+
+/// Returns the sorted primes found by the rounds.
+fn round_primes(rounds : &[Round]) -> Vec<PrimeImplicant> {
+    let mut primes = rounds.iter().flat_map(|r| r.primes.iter().cloned()).collect::<Vec<PrimeImplicant>>();
+    primes.sort();
+    primes.dedup();
+    primes
+}
+
+/// Returns the variables of the equation and its minterms, in the order of the truth table.
+pub fn minterms(eq : &Equation) -> Result<(Vec<String>, Vec<PrimeImplicant>), String> {
+    let compiled = Compiled::new(eq);
+    let table = compiled.truth_table()?;
+    let nb_vars = compiled.vars().len();
+    let impls = (0..1usize << nb_vars)
+        .filter(|a| (table[a / 64] >> (a % 64)) & 1 == 1)
+        .map(|a| {
+            let list = (0..nb_vars)
+                .map(|i| match (a >> i) & 1 {
+                    1 => Present::Yes,
+                    _ => Present::No,
+                })
+                .collect();
+            PrimeImplicant::new(list)
+        })
+        .collect();
+    Ok((compiled.vars().clone(), impls))
+}
+
+/// Returns the implicants found by merging the given ones until no merge is possible, the
+/// minterms giving all the prime implicants of their sum.
+pub fn mccluskey_primes(impls : Vec<PrimeImplicant>)-> Vec<PrimeImplicant>{
+    mccluskey_primes_checked(impls, &mut || true).unwrap()
+}
+
+/// Same as mccluskey_primes, calling check before every round and returning None as soon as it
+/// returns false.
+pub fn mccluskey_primes_checked(impls : Vec<PrimeImplicant>, check : &mut dyn FnMut() -> bool) -> Option<Vec<PrimeImplicant>> {
+    Some(round_primes(&rounds_checked(impls, false, check)?))
+}
+
+
+/// Two implicants and the result of their merge.
+pub type Merge = (PrimeImplicant, PrimeImplicant, PrimeImplicant);
+
+/// A round of mccluskey_primes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Round {
+    /// The implicants at the start of the round, as given by sort_prime_implicants.
+    pub groups : Vec<Vec<PrimeImplicant>>,
+    pub merges : Vec<Merge>,
+    /// The implicants of the round that did not merge.
+    pub primes : Vec<PrimeImplicant>,
+}
+
+/// Every step of the algorithm, to explain how the result was found.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tabulation {
+    pub vars : Vec<String>,
+    /// The implicants given to the algorithm.
+    pub terms : Vec<PrimeImplicant>,
+    pub rounds : Vec<Round>,
+    /// The result of mccluskey_primes.
+    pub primes : Vec<PrimeImplicant>,
+    /// The primes that are the only ones to contain one of the terms.
+    pub essentials : Vec<PrimeImplicant>,
+    /// The essentials and, while a term is not covered, the prime containing the most uncovered
+    /// terms.
+    pub selection : Vec<PrimeImplicant>,
+}
+
+/// Same as mccluskey_implicants but records every round.
+pub fn mccluskey_verbose((vars, impls) : (Vec<String>, Vec<PrimeImplicant>)) -> Tabulation {
+    let rounds = rounds_checked(impls, true, &mut || true).unwrap();
+    let terms = rounds.first().map_or(vec![], |r| r.groups.concat());
+    let primes = round_primes(&rounds);
+    let (essentials, selection) = select_cover(&terms, &primes);
+    Tabulation { vars, terms, rounds, primes, essentials, selection }
+}
+
+impl Tabulation {
+    /// Tabulation of the minterms of the equation.
+    pub fn from_eq(eq : &Equation) -> Result<Self, String> {
+        Ok(mccluskey_verbose(minterms(eq)?))
+    }
+
+    /// Returns the sum of the implicants, eg. a * !b + c.
+    fn sum(&self, impls : &[PrimeImplicant]) -> String {
+        let vars = self.vars.iter().collect();
+        let eq = Equation::any(impls.iter().map(|p| p.clone().to_eq(&vars)));
+        eq.pretty(Style::Arithmetic)
+    }
+
+    pub fn to_text(&self) -> String {
+        self.render(false)
+    }
+
+    pub fn to_markdown(&self) -> String {
+        self.render(true)
+    }
+
+    fn render(&self, markdown : bool) -> String {
+        let code = |p : &PrimeImplicant| match markdown {
+            true => format!("`{}`", p.get_string()),
+            false => p.get_string(),
+        };
+        let title = |t : &str| match markdown {
+            true => format!("### {}\n\n", t),
+            false => format!("{}\n", t),
+        };
+        let item = match markdown {
+            true => "- ",
+            false => "  ",
+        };
+        let mut text = format!("variables : {}\n\n", self.vars.join(", "));
+        for (i, round) in self.rounds.iter().enumerate() {
+            text.push_str(&title(&format!("Round {}", i + 1)));
+            let mut impls = round.groups.concat();
+            impls.sort_by_key(|p| (p.nb_yes(), p.get_string()));
+            let mut ones = impls.iter().map(|p| p.nb_yes()).collect::<Vec<usize>>();
+            ones.dedup();
+            if markdown {
+                text.push_str("| ones | implicants |\n|---|---|\n");
+            }
+            for n in ones {
+                let group = impls.iter().filter(|p| p.nb_yes() == n).map(&code).collect::<Vec<String>>();
+                match markdown {
+                    true => text.push_str(&format!("| {} | {} |\n", n, group.join(", "))),
+                    false => text.push_str(&format!("  {} ones : {}\n", n, group.join(" "))),
+                }
+            }
+            if markdown {
+                text.push('\n');
+            }
+            for (a, b, merged) in round.merges.iter() {
+                text.push_str(&format!("{}merged {} and {} into {}\n", item, code(a), code(b), code(merged)));
+            }
+            for prime in round.primes.iter() {
+                text.push_str(&format!("{}prime {}\n", item, code(prime)));
+            }
+            text.push('\n');
+        }
+        text.push_str(&title("Prime implicant chart"));
+        let terms = self.terms.iter().map(|t| t.get_string()).collect::<Vec<String>>();
+        let width = self.vars.len().max(1) + 2;
+        match markdown {
+            true => text.push_str(&format!("| | {} |\n|---|{}\n", terms.join(" | "), "---|".repeat(terms.len()))),
+            false => {
+                let header = terms.iter().map(|t| format!("{:<w$}", t, w = width)).collect::<String>();
+                text.push_str(&format!("{:<w$}{}\n", "", header.trim_end(), w = width + 2));
+            }
         }
-        size_before = to_add.len();
-        sorted_primes = sort_prime_implicants(to_add);
+        for prime in self.primes.iter() {
+            let marks = self.terms.iter().map(|t| match prime.contains(t) {
+                true => "X",
+                false => "",
+            }).collect::<Vec<&str>>();
+            match markdown {
+                true => text.push_str(&format!("| {} | {} |\n", code(prime), marks.join(" | "))),
+                false => {
+                    let row = marks.iter().map(|m| format!("{:<w$}", m, w = width)).collect::<String>();
+                    let line = format!("  {:<w$}{}", prime.get_string(), row, w = width);
+                    text.push_str(line.trim_end());
+                    text.push('\n');
+                }
+            }
+        }
+        text.push('\n');
+        text.push_str(&title("Essential prime implicants"));
+        let vars = self.vars.iter().collect();
+        for prime in self.essentials.iter() {
+            let eq = prime.clone().to_eq(&vars).pretty(Style::Arithmetic);
+            text.push_str(&format!("{}{} = {}\n", item, code(prime), eq));
+        }
+        text.push('\n');
+        text.push_str(&title("Result"));
+        text.push_str(&format!("f = {}\n", self.sum(&self.selection)));
+        text
     }
 }
 
 
-
 #[cfg(test)]
 mod tests_mccluskey_primes {
     use super::*;
@@ -231,6 +546,30 @@ mod tests_mccluskey_primes {
                      prime_from_prod("!b*c"),
                      prime_from_prod("b*c")]), "((a * ! c) + c)");
     }
+
+    #[test]
+    fn test_checked() {
+        let (_, terms) = minterms(&Equation::from("a + b + c + d".to_string())).unwrap();
+        let mut calls = 0;
+        assert!(mccluskey_primes_checked(terms, &mut || { calls += 1; calls < 3 }).is_none());
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn test_primes_of_cubes() {
+        // the cubes are merged as given, a * b being a prime of the sum found only from the minterms
+        let primes = mccluskey_primes(vec![prime_from_prod("a*b*!c"), prime_from_prod("a*!b*!c"), prime_from_prod("b*c")]);
+        assert_eq!(primes.iter().map(|p| p.get_string()).collect::<Vec<String>>(), vec!["1x0xx", "x11xx"]);
+    }
+
+    #[test]
+    fn test_wide_cubes() {
+        // the 2^40 minterms are never built
+        let vars = (0..40).map(|i| format!("x{}", i)).collect::<Vec<String>>();
+        let impls = (0..40).map(|i| PrimeImplicant::any_with(i, Present::Yes, 40)).collect::<Vec<PrimeImplicant>>();
+        assert_eq!(mccluskey((vars, impls.clone())).len(), 40);
+        assert_eq!(mccluskey_primes(impls).len(), 40);
+    }
 }
 
 
@@ -342,4 +681,56 @@ mod tests_from_eq {
                         nb_yes : 3,
                     })
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests_tabulation {
+    use super::*;
+
+    #[test]
+    fn test_rounds() {
+        let tab = Tabulation::from_eq(&Equation::from("!a*!b*c + a*!b*!c + a*!b*c + a*b*c".to_string())).unwrap();
+        assert_eq!(tab.vars, vec!["a", "b", "c"]);
+        assert_eq!(tab.terms.len(), 4);
+        let first = &tab.rounds[0];
+        assert_eq!(first.groups.concat().len(), 4);
+        assert!(first.merges.iter().all(|(a, b, m)| a.can_merge(b) && m.contains(a) && m.contains(b)));
+        let strings = |impls : &[PrimeImplicant]| impls.iter().map(|p| p.get_string()).collect::<Vec<String>>();
+        // 100, 001 and 111 are each contained in a single prime
+        assert_eq!(strings(&tab.primes), vec!["10x", "1x1", "x01"]);
+        assert_eq!(strings(&tab.essentials), vec!["10x", "1x1", "x01"]);
+        assert_eq!(strings(&tab.selection), vec!["10x", "1x1", "x01"]);
+    }
+
+    #[test]
+    fn test_every_pair_merged() {
+        let tab = Tabulation::from_eq(&Equation::from("a*b*c + a*b*!c + a*!b*c".to_string())).unwrap();
+        let strings = |impls : &[PrimeImplicant]| impls.iter().map(|p| p.get_string()).collect::<Vec<String>>();
+        // 111 merges with both 110 and 101
+        assert_eq!(tab.rounds[0].merges.len(), 2);
+        assert!(tab.rounds[0].primes.is_empty());
+        assert_eq!(strings(&tab.primes), vec!["11x", "1x1"]);
+        assert_eq!(strings(&tab.essentials), vec!["11x", "1x1"]);
+        assert_eq!(strings(&tab.selection), vec!["11x", "1x1"]);
+        assert!(tab.to_text().ends_with("Result\nf = a * b + a * c\n"));
+    }
+
+    #[test]
+    fn test_report() {
+        let tab = Tabulation::from_eq(&Equation::from("a*b + a*!b".to_string())).unwrap();
+        assert_eq!(
+            tab.to_text(),
+            "variables : a, b\n\n\
+             Round 1\n  1 ones : 10\n  2 ones : 11\n  merged 10 and 11 into 1x\n\n\
+             Round 2\n  1 ones : 1x\n  prime 1x\n\n\
+             Prime implicant chart\n      10  11\n  1x  X   X\n\n\
+             Essential prime implicants\n  1x = a\n\n\
+             Result\nf = a\n"
+        );
+        let markdown = tab.to_markdown();
+        assert!(markdown.contains("### Round 1\n\n| ones | implicants |\n|---|---|\n| 1 | `10` |\n"));
+        assert!(markdown.contains("- merged `10` and `11` into `1x`\n"));
+        assert!(markdown.contains("| | 10 | 11 |\n|---|---|---|\n| `1x` | X | X |\n"));
+        assert!(markdown.ends_with("### Result\n\nf = a\n"));
+    }
+}
@@ -4,6 +4,8 @@ use lexer::Token;
 pub struct EqParser {
     tokens: Vec<Token>,
     pos: usize,
+    /// Adjacent literals are a product, as in ab + (c + d)e.
+    juxtaposition: bool,
 }
 
 impl EqParser {
@@ -11,9 +13,40 @@ impl EqParser {
         EqParser {
             tokens: tokens,
             pos: 0,
+            juxtaposition: false,
         }
     }
 
+    /// Reads adjacent literals as a product, the names made of several letters being split in
+    /// single letter variables : ab'c => a * !b * c.
+    pub fn with_juxtaposition(mut self) -> Self {
+        self.juxtaposition = true;
+        self.tokens = self
+            .tokens
+            .into_iter()
+            .flat_map(|t| match t {
+                Token::Ident(ref name) if name.chars().all(|c| c.is_ascii_alphabetic()) => name
+                    .chars()
+                    .map(|c| Token::Ident(c.to_string()))
+                    .collect::<Vec<Token>>(),
+                t => vec![t],
+            })
+            .collect();
+        self
+    }
+
+    /// True if the next token can start a literal, which is a product by juxtaposition.
+    fn starts_literal(&mut self) -> bool {
+        matches!(
+            self.peek(),
+            Some(&Token::Ident(_))
+                | Some(&Token::LParen)
+                | Some(&Token::Not)
+                | Some(&Token::True)
+                | Some(&Token::False)
+        )
+    }
+
     /// Peeks once for next char in the source but do not advance.
     fn peek(&mut self) -> Option<&Token> {
         if self.is_at_end() {
@@ -28,60 +61,151 @@ impl EqParser {
         !self.is_at_end() && self.peek() == Some(token)
     }
 
-    /// Advance and consume a char, returning it.
-    fn advance(&mut self) -> &Token {
+    /// Advance and consume a token, returning it.
+    fn advance(&mut self) -> Option<&Token> {
         self.pos += 1;
-        &self.tokens[self.pos - 1]
+        self.tokens.get(self.pos - 1)
     }
 
     pub fn is_at_end(&self) -> bool {
         self.pos >= self.tokens.len()
     }
 
+    /// Parses the first equation of the tokens, ignoring the ones after it.
     pub fn parse(&mut self) -> Equation {
-        self.sum()
+        self.sum().unwrap_or_else(|e| panic!("{}", e))
     }
 
-    pub fn sum(&mut self) -> Equation {
-        let mut sm = vec![self.prod()];
+    /// Parses the whole list of tokens, returning an error instead of panicking.
+    pub fn try_parse(&mut self) -> Result<Equation, String> {
+        let eq = self.sum()?;
+        match self.peek().cloned() {
+            None => Ok(eq),
+            Some(u) => Err(format!("error, unexpected {:?} at : {}", u, self.pos)),
+        }
+    }
+
+    /// Parses a list of Verilog statements of the form assign y = a & b;
+    pub fn assignments(&mut self) -> Result<Vec<(String, Equation)>, String> {
+        let mut assigns = vec![];
+        while !self.is_at_end() {
+            self.expect(&Token::Assign)?;
+            let pos = self.pos;
+            let name = match self.advance() {
+                Some(Token::Ident(s)) => s.to_string(),
+                u => return Err(format!("error, expected variable, found {:?} at : {}", u, pos)),
+            };
+            self.expect(&Token::Equal)?;
+            let eq = self.sum()?;
+            self.expect(&Token::Semicolon)?;
+            assigns.push((name, eq));
+        }
+        Ok(assigns)
+    }
+
+    /// Consumes the next token, failing if it is not the expected one.
+    fn expect(&mut self, token: &Token) -> Result<(), String> {
+        if !self.check(token) {
+            return Err(format!(
+                "error, expected {:?}, found {:?} at : {}",
+                token,
+                self.peek().cloned(),
+                self.pos
+            ));
+        }
+        self.advance();
+        Ok(())
+    }
+
+    pub fn sum(&mut self) -> Result<Equation, String> {
+        let mut sm = vec![self.xor()?];
         while self.check(&Token::Or) {
             self.advance();
-            sm.push(self.prod());
+            sm.push(self.xor()?);
         }
-        Equation::Sum(Box::new(Sum::new(sm)))
+        Ok(Equation::Sum(Box::new(Sum::new(sm))))
     }
 
-    pub fn prod(&mut self) -> Equation {
-        let mut sm = vec![self.not()];
-        while self.check(&Token::And) {
+    /// Exclusive or binds tighter than the sum, it is expanded to a sum of products :
+    /// a ^ b => (a * !b) + (!a * b)
+    /// The operands of a chain are combined pairwise, as a left fold would double the size of the
+    /// expansion at every operand. A chain containing an odd number of xnor is negated.
+    pub fn xor(&mut self) -> Result<Equation, String> {
+        let mut operands = vec![self.prod()?];
+        let mut negated = false;
+        loop {
+            match self.peek() {
+                Some(&Token::Xor) => (),
+                Some(&Token::Xnor) => negated = !negated,
+                _ => break,
+            }
             self.advance();
-            sm.push(self.not());
+            operands.push(self.prod()?);
         }
-        Equation::Prod(Box::new(Prod::new(sm)))
+        while operands.len() > 1 {
+            let mut pairs = operands.into_iter();
+            operands = vec![];
+            while let Some(left) = pairs.next() {
+                operands.push(match pairs.next() {
+                    Some(right) => left ^ right,
+                    None => left,
+                });
+            }
+        }
+        let eq = operands.pop().unwrap();
+        Ok(match negated {
+            true => !eq,
+            false => eq,
+        })
     }
 
-    pub fn not(&mut self) -> Equation {
+    pub fn prod(&mut self) -> Result<Equation, String> {
+        let mut sm = vec![self.not()?];
+        loop {
+            if self.check(&Token::And) {
+                self.advance();
+            } else if !self.juxtaposition || !self.starts_literal() {
+                break;
+            }
+            sm.push(self.not()?);
+        }
+        Ok(Equation::Prod(Box::new(Prod::new(sm))))
+    }
+
+    pub fn not(&mut self) -> Result<Equation, String> {
         match self.check(&Token::Not) {
             true => {
                 self.advance();
-                Equation::Not(Box::new(Not::new(self.not())))
+                Ok(Equation::Not(Box::new(Not::new(self.not()?))))
             }
-            false => self.literal(),
+            false => self.postfix(),
+        }
+    }
+
+    /// A literal followed by primes, a'' being !!a.
+    pub fn postfix(&mut self) -> Result<Equation, String> {
+        let mut eq = self.literal()?;
+        while self.check(&Token::Prime) {
+            self.advance();
+            eq = Equation::Not(Box::new(Not::new(eq)));
         }
+        Ok(eq)
     }
 
-    pub fn literal(&mut self) -> Equation {
+    pub fn literal(&mut self) -> Result<Equation, String> {
         let pos = self.pos;
         match self.check(&Token::LParen) {
             true => {
                 self.advance();
-                let inner = self.sum();
-                self.advance();
-                inner
+                let inner = self.sum()?;
+                self.expect(&Token::RParen)?;
+                Ok(inner)
             }
             false => match self.advance() {
-                Token::Ident(s) => Equation::Var(s.to_string()),
-                u => panic!(format!(
+                Some(Token::Ident(s)) => Ok(Equation::Var(s.to_string())),
+                Some(Token::True) => Ok(Equation::constant(true)),
+                Some(Token::False) => Ok(Equation::constant(false)),
+                u => Err(format!(
                     "error, expected variable or rparen, found {:?} at : {}",
                     u, pos
                 )),
@@ -132,3 +256,107 @@ mod tests_parser {
         );
     }
 }
+
+#[cfg(test)]
+mod tests_verilog_parser {
+    use super::*;
+    use equation::Equation;
+    use lexer::{Dialect, EqLexer};
+    use rand::random;
+    use std::collections::HashMap;
+
+    fn verilog(text: &str) -> Vec<(String, Equation)> {
+        EqParser::new(
+            EqLexer::with_dialect(text.to_string(), Dialect::Verilog)
+                .get_tokens()
+                .unwrap(),
+        ).assignments()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_xor() {
+        let assigns = verilog("assign y = a ^ b & c;\nassign z[0] = ~(a ~^ b) | 1'b0;");
+        assert_eq!(assigns[0].0, "y");
+        assigns[0].1.compare_random_values(&Equation::from("a*!(b*c) + !a*b*c".to_string()), 100);
+        assert_eq!(assigns[1].0, "z[0]");
+        assigns[1].1.compare_random_values(&Equation::from("a*!b + !a*b".to_string()), 100);
+    }
+
+    #[test]
+    fn test_xor_chain() {
+        let names = (0..16).map(|i| format!("x{}", i)).collect::<Vec<String>>();
+        let assigns = verilog(&format!("assign y = {};", names.join(" ^ ")));
+        let mut nodes = 0;
+        assigns[0].1.walk(&mut |_| nodes += 1);
+        assert!(nodes < 2000, "{} nodes", nodes);
+        for _ in 0..100 {
+            let values = names.iter().map(|n| (n.clone(), random::<u64>() % 2 == 1)).collect::<HashMap<String, bool>>();
+            let parity = values.values().filter(|&&v| v).count() % 2 == 1;
+            assert_eq!(assigns[0].1.eval(&values), parity);
+        }
+        let assigns = verilog("assign y = a ~^ b ^ c ~^ d;");
+        assigns[0].1.compare_random_values(&verilog("assign y = a ^ b ^ c ^ d;")[0].1, 100);
+        let assigns = verilog("assign y = a ~^ b ^ c;");
+        assigns[0].1.compare_random_values(&Equation::from("!(a*!b + !a*b)*!c + (a*!b + !a*b)*c".to_string()), 100);
+    }
+
+    #[test]
+    fn test_errors_as_results() {
+        assert!(Equation::from_verilog("a & (b".to_string()).is_err());
+        assert!(Equation::from_verilog("a + b".to_string()).is_err());
+        assert!(Equation::from_verilog_assigns("assign y = a[3:0];".to_string()).is_err());
+        assert!(Equation::from_verilog_assigns("assign y = a & 1'b1;".to_string()).is_ok());
+    }
+
+    #[test]
+    fn test_bit_select() {
+        let assigns = verilog("assign carry = bus[0] & bus[1] | bus[2] & (bus[0] ^ bus[1]);");
+        assert_eq!(assigns[0].1.get_vars(), vec!["bus[0]", "bus[1]", "bus[2]"]);
+    }
+
+    #[test]
+    fn test_missing_semicolon() {
+        assert!(Equation::from_verilog_assigns("assign y = a & b".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_errors() {
+        let parse = |text: &str| EqParser::new(EqLexer::new(text.to_string()).get_tokens().unwrap()).try_parse();
+        assert!(parse("(a + b").is_err());
+        assert!(parse("a + ").is_err());
+        assert!(parse("a b").is_err());
+        assert!(parse("a + b)").is_err());
+        assert!(parse("(a + b)").is_ok());
+        assert!(parse("'a").is_err());
+    }
+
+    #[test]
+    fn test_juxtaposition() {
+        let parse = |text: &str| {
+            EqParser::new(EqLexer::new(text.to_string()).get_tokens().unwrap())
+                .with_juxtaposition()
+                .parse()
+        };
+        parse("ab + c").compare_random_values(&Equation::from("a*b + c".to_string()), 100);
+        parse("a b + !c").compare_random_values(&Equation::from("a*b + !c".to_string()), 100);
+        parse("ab'c + (a + b)c'").compare_random_values(&Equation::from("a*!b*c + (a+b)*!c".to_string()), 100);
+        parse("x1 x2 * (a)(b)").compare_random_values(&Equation::from("x1*x2*a*b".to_string()), 100);
+        assert!(EqParser::new(EqLexer::new("a b".to_string()).get_tokens().unwrap()).try_parse().is_err());
+    }
+
+    #[test]
+    fn test_alternative_syntax() {
+        let eq = Equation::from("¬a ∧ (b ∨ c)".to_string());
+        eq.compare_random_values(&Equation::from("!a * (b + c)".to_string()), 100);
+        let eq = Equation::from("~a && b || c'' * (a + b)'".to_string());
+        eq.compare_random_values(&Equation::from("!a * b + c * !a * !b".to_string()), 100);
+        let eq = Equation::from("x_1 * bus[3] ⊕ y".to_string());
+        assert_eq!(eq.get_vars(), vec!["bus[3]", "x_1", "y"]);
+        let eq = Equation::from("a.b".to_string());
+        assert_eq!(eq.get_vars(), vec!["a", "b"]);
+        assert!(eq.is_equivalent(&Equation::from("a * b".to_string())));
+        let eq = Equation::from_verilog("ctrl.valid & bus[3]".to_string()).unwrap();
+        assert_eq!(eq.get_vars(), vec!["bus[3]", "ctrl.valid"]);
+    }
+}
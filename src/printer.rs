@@ -0,0 +1,201 @@
+//! Prints equations with as few parentheses as the precedence of the operators allows.
+
+use equation::Equation;
+
+/// The notation used for the operators.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Style {
+    /// a * !b + c
+    Arithmetic,
+    /// a & !b | c
+    Logic,
+    /// a and not b or c
+    Words,
+    /// a * b' + c
+    Postfix,
+    /// ab' + c, only readable with single letter variables.
+    Juxtaposition,
+    /// ¬a ∧ b ∨ c
+    Unicode,
+    /// \overline{a}b + c
+    Latex,
+    /// \lnot a \land b \lor c
+    LatexLogic,
+}
+
+/// How the negation is written.
+enum Negation {
+    Prefix(&'static str),
+    Postfix,
+    Overline,
+}
+
+/// Precedence levels, an operand of lower level than its parent gets parentheses.
+const SUM: u8 = 1;
+const PROD: u8 = 2;
+const NOT: u8 = 3;
+const ATOM: u8 = 4;
+
+impl Style {
+    fn or(self) -> &'static str {
+        match self {
+            Style::Logic => " | ",
+            Style::Words => " or ",
+            Style::Unicode => " ∨ ",
+            Style::LatexLogic => " \\lor ",
+            _ => " + ",
+        }
+    }
+
+    fn and(self) -> &'static str {
+        match self {
+            Style::Logic => " & ",
+            Style::Words => " and ",
+            Style::Unicode => " ∧ ",
+            Style::LatexLogic => " \\land ",
+            Style::Juxtaposition | Style::Latex => "",
+            _ => " * ",
+        }
+    }
+
+    fn not(self) -> Negation {
+        match self {
+            Style::Arithmetic | Style::Logic => Negation::Prefix("!"),
+            Style::Words => Negation::Prefix("not "),
+            Style::Unicode => Negation::Prefix("¬"),
+            Style::LatexLogic => Negation::Prefix("\\lnot "),
+            Style::Postfix | Style::Juxtaposition => Negation::Postfix,
+            Style::Latex => Negation::Overline,
+        }
+    }
+
+    /// In LaTeX the names of several letters are written in italic and the underscores escaped.
+    fn var(self, name: &str) -> String {
+        match self {
+            Style::Latex | Style::LatexLogic => latex_var(name),
+            _ => name.to_string(),
+        }
+    }
+
+    /// Returns the text of the equation and its precedence level.
+    fn print(self, eq: &Equation) -> (String, u8) {
+        if let Some(value) = eq.as_constant() {
+            return ((value as u8).to_string(), ATOM);
+        }
+        match *eq {
+            Equation::Var(ref v) => (self.var(v), ATOM),
+            Equation::Not(_) => match self.not() {
+                Negation::Prefix(prefix) => (format!("{}{}", prefix, self.operand(eq.inners()[0], NOT)), NOT),
+                Negation::Postfix => (format!("{}'", self.operand(eq.inners()[0], ATOM)), ATOM),
+                Negation::Overline => (format!("\\overline{{{}}}", self.print(eq.inners()[0]).0), ATOM),
+            },
+            // a single operand does not need its operator
+            _ if eq.inners().len() == 1 => self.print(eq.inners()[0]),
+            Equation::Sum(_) | Equation::Prod(_) => {
+                let (op, level) = match *eq {
+                    Equation::Sum(_) => (self.or(), SUM),
+                    _ => (self.and(), PROD),
+                };
+                let inner = eq
+                    .inners()
+                    .into_iter()
+                    .map(|i| self.operand(i, level))
+                    .collect::<Vec<String>>();
+                (inner.join(op), level)
+            }
+        }
+    }
+
+    /// Prints an operand of an operator of the given level.
+    fn operand(self, eq: &Equation, level: u8) -> String {
+        match self.print(eq) {
+            (text, inner) if inner < level => format!("({})", text),
+            (text, _) => text,
+        }
+    }
+}
+
+/// Returns the name of a variable for a LaTeX formula.
+pub fn latex_var(name: &str) -> String {
+    let escaped = name.replace('_', "\\_");
+    match name.chars().count() {
+        1 => escaped,
+        _ => format!("\\mathit{{{}}}", escaped),
+    }
+}
+
+impl Equation {
+    /// Prints the equation in the given style with the minimal parentheses.
+    pub fn pretty(&self, style: Style) -> String {
+        style.print(self).0
+    }
+}
+
+#[cfg(test)]
+mod tests_printer {
+    use super::*;
+    use rand::random;
+
+    #[test]
+    fn test_styles() {
+        let eq = Equation::from("a * !b + !(c + d) * e".to_string());
+        assert_eq!(eq.pretty(Style::Arithmetic), "a * !b + !(c + d) * e");
+        assert_eq!(eq.pretty(Style::Logic), "a & !b | !(c | d) & e");
+        assert_eq!(eq.pretty(Style::Words), "a and not b or not (c or d) and e");
+        assert_eq!(eq.pretty(Style::Postfix), "a * b' + (c + d)' * e");
+        assert_eq!(eq.pretty(Style::Juxtaposition), "ab' + (c + d)'e");
+        assert_eq!(eq.pretty(Style::Unicode), "a ∧ ¬b ∨ ¬(c ∨ d) ∧ e");
+        assert_eq!(eq.pretty(Style::Latex), "a\\overline{b} + \\overline{c + d}e");
+        assert_eq!(eq.pretty(Style::LatexLogic), "a \\land \\lnot b \\lor \\lnot (c \\lor d) \\land e");
+    }
+
+    #[test]
+    fn test_latex_vars() {
+        let eq = Equation::from("!carry * x".to_string());
+        assert_eq!(eq.pretty(Style::Latex), "\\overline{\\mathit{carry}}x");
+        assert_eq!(latex_var("c_in"), "\\mathit{c\\_in}");
+    }
+
+    #[test]
+    fn test_parentheses() {
+        let pretty = |text: &str| Equation::from(text.to_string()).pretty(Style::Arithmetic);
+        assert_eq!(pretty("(a + b) * (c + (d * e))"), "(a + b) * (c + d * e)");
+        assert_eq!(pretty("!!a * !(b * c)"), "!!a * !(b * c)");
+        assert_eq!(pretty("((a)) + 0"), "a + 0");
+        assert_eq!(pretty("!(a + !b)"), "!(a + !b)");
+        assert_eq!(Equation::constant(true).pretty(Style::Words), "1");
+    }
+
+    /// Returns a random equation of at most the given depth over the variables a to e.
+    fn random_eq(depth: usize) -> Equation {
+        let var = || Equation::var(&((b'a' + (random::<u64>() % 5) as u8) as char).to_string());
+        if depth == 0 {
+            return var();
+        }
+        let operands = || (0..1 + random::<u64>() % 3).map(|_| random_eq(depth - 1)).collect::<Vec<_>>();
+        match random::<u64>() % 4 {
+            0 => var(),
+            1 => !random_eq(depth - 1),
+            2 => Equation::all(operands()),
+            _ => Equation::any(operands()),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for _ in 0..200 {
+            let eq = random_eq(4);
+            let styles = [Style::Arithmetic, Style::Logic, Style::Words, Style::Postfix, Style::Unicode];
+            for &style in styles.iter() {
+                let text = eq.pretty(style);
+                let parsed = Equation::from(text.clone());
+                assert_eq!(parsed.pretty(style), text);
+                parsed.compare_random_values(&eq, 100);
+            }
+            let text = eq.pretty(Style::Juxtaposition);
+            let parsed = Equation::from_juxtaposition(text.clone());
+            assert_eq!(parsed.pretty(Style::Juxtaposition), text);
+            parsed.compare_random_values(&eq, 100);
+        }
+    }
+}
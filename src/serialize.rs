@@ -0,0 +1,127 @@
+//! Serde support, enabled by the serde feature.
+//!
+//! Equations use the tagged form derived on the enum, prime implicants are written as the string
+//! of their values (eg. "10-" for a * !b) and the text module stores an equation as its printed
+//! expression.
+
+use equation::Equation;
+use mccluskey::{PrimeImplicant, Present};
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+use std::fmt;
+
+impl Serialize for PrimeImplicant {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let text = self
+            .list()
+            .iter()
+            .map(|p| match *p {
+                Present::Yes => '1',
+                Present::No => '0',
+                Present::Any => '-',
+            })
+            .collect::<String>();
+        serializer.serialize_str(&text)
+    }
+}
+
+struct PrimeVisitor;
+
+impl<'de> Visitor<'de> for PrimeVisitor {
+    type Value = PrimeImplicant;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a string of 0, 1 and -")
+    }
+
+    fn visit_str<E: de::Error>(self, text: &str) -> Result<PrimeImplicant, E> {
+        let list = text
+            .chars()
+            .map(|c| match c {
+                '1' => Ok(Present::Yes),
+                '0' => Ok(Present::No),
+                '-' => Ok(Present::Any),
+                _ => Err(E::custom(format!("invalid value in implicant : {}", c))),
+            })
+            .collect::<Result<Vec<Present>, E>>()?;
+        Ok(PrimeImplicant::new(list))
+    }
+}
+
+impl<'de> Deserialize<'de> for PrimeImplicant {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(PrimeVisitor)
+    }
+}
+
+/// Stores an equation as its printed expression, to be used with #[serde(with = "text")].
+pub mod text {
+    use equation::Equation;
+    use printer::Style;
+    use serde::de::{self, Deserialize, Deserializer};
+    use serde::ser::Serializer;
+
+    pub fn serialize<S: Serializer>(eq: &Equation, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&eq.pretty(Style::Arithmetic))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Equation, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        Equation::parse(&text).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests_serialize {
+    use super::*;
+    use serde_json;
+
+    #[derive(Serialize, Deserialize)]
+    struct Config {
+        #[serde(with = "text")]
+        eq: Equation,
+    }
+
+    const EQUATIONS: [&str; 4] = [
+        "a * !b + c",
+        "I & !B | (A + B) and (c + a./y) + !(x * z)",
+        "!(a + !!b) * 1",
+        "a * b + 0",
+    ];
+
+    #[test]
+    fn test_json() {
+        let eq = Equation::from("a * !b".to_string());
+        let json = serde_json::to_string(&eq).unwrap();
+        assert_eq!(json, r#"{"sum":[{"prod":[{"var":"a"},{"not":{"var":"b"}}]}]}"#);
+        for text in EQUATIONS.iter() {
+            let eq = Equation::from(text.to_string());
+            let json = serde_json::to_string(&eq).unwrap();
+            assert_eq!(serde_json::from_str::<Equation>(&json).unwrap(), eq);
+        }
+    }
+
+    #[test]
+    fn test_text() {
+        for text in EQUATIONS.iter() {
+            let eq = Equation::from(text.to_string());
+            let json = serde_json::to_string(&Config { eq: eq.clone() }).unwrap();
+            let read = serde_json::from_str::<Config>(&json).unwrap().eq;
+            assert_eq!(read, eq);
+            assert!(read.is_equivalent(&eq));
+        }
+        let json = serde_json::to_string(&Config { eq: Equation::from("a * !b".to_string()) }).unwrap();
+        assert_eq!(json, r#"{"eq":"a * !b"}"#);
+        assert!(serde_json::from_str::<Config>(r#"{"eq":"(a + "}"#).is_err());
+    }
+
+    #[test]
+    fn test_implicants() {
+        let prime = PrimeImplicant::new(vec![Present::Yes, Present::No, Present::Any]);
+        let json = serde_json::to_string(&prime).unwrap();
+        assert_eq!(json, r#""10-""#);
+        assert_eq!(serde_json::from_str::<PrimeImplicant>(&json).unwrap(), prime);
+        assert!(serde_json::from_str::<PrimeImplicant>(r#""1x""#).is_err());
+        assert_eq!(serde_json::to_string(&Present::Any).unwrap(), r#""any""#);
+    }
+}
@@ -0,0 +1,864 @@
+//! Simplification as a list of passes chosen by the user, each pass rewriting the whole tree.
+
+use bdd;
+use bdd::Bdd;
+use equation::Equation;
+use printer::Style;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+use trace::Trace;
+
+/// A rewrite of the whole equation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pass {
+    /// Pushes the negations down to the variables with De Morgan's laws.
+    Nnf,
+    /// Merges the nested sums and products and removes the operators of a single operand.
+    Flatten,
+    /// Removes from the sums of cubes the contradictions and the cubes containing another one,
+    /// and from the products of clauses the tautologies and the clauses containing another one.
+    Absorption,
+    /// Distributes the products over the sums, giving a sum of products.
+    Distribution,
+    /// Minimizes the sums of cubes with the mccluskey algorithm : their cubes are merged until no
+    /// merge is possible, the cover being the essential ones completed greedily.
+    Mccluskey,
+    /// Minimizes the sums of cubes heuristically by expanding their cubes then removing the
+    /// redundant ones.
+    Espresso,
+    /// Factors out of the sums of cubes the literals shared by several cubes.
+    Factoring,
+}
+
+/// Reads the name of a pass in lower case, eg. espresso.
+impl FromStr for Pass {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, String> {
+        match name {
+            "nnf" => Ok(Pass::Nnf),
+            "flatten" => Ok(Pass::Flatten),
+            "absorption" => Ok(Pass::Absorption),
+            "distribution" => Ok(Pass::Distribution),
+            "mccluskey" => Ok(Pass::Mccluskey),
+            "espresso" => Ok(Pass::Espresso),
+            "factoring" => Ok(Pass::Factoring),
+            _ => Err(format!("unknown pass : {}", name)),
+        }
+    }
+}
+
+/// The form of the result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Form {
+    /// A sum of products, eg. a * b + !c
+    Sop,
+    /// A product of sums, obtained from the sum of products of the negation.
+    Pos,
+    /// Any tree, as left by the passes.
+    MultiLevel,
+}
+
+/// Reads sop, pos or multilevel.
+impl FromStr for Form {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, String> {
+        match name {
+            "sop" => Ok(Form::Sop),
+            "pos" => Ok(Form::Pos),
+            "multilevel" => Ok(Form::MultiLevel),
+            _ => Err(format!("unknown form : {}", name)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimplifyOptions {
+    pub passes: Vec<Pass>,
+    pub form: Form,
+    /// The passes are run again until the equation stops changing, at most this number of times.
+    pub max_rounds: usize,
+    /// The sums of more variables are minimized by Espresso instead of Mccluskey.
+    pub max_exact_vars: Option<usize>,
+    pub budget: Budget,
+}
+
+impl SimplifyOptions {
+    pub fn new(passes: Vec<Pass>) -> Self {
+        SimplifyOptions {
+            passes,
+            form: Form::Sop,
+            max_rounds: 1,
+            max_exact_vars: None,
+            budget: Budget::default(),
+        }
+    }
+}
+
+/// A flag stopping the simplifications holding a clone of it, eg. from another thread.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        CancelToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Two tokens are equal if they are clones of each other.
+impl PartialEq for CancelToken {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// The limits of a simplification, none by default.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Budget {
+    /// The largest number of products a distribution may create.
+    pub max_terms: Option<usize>,
+    /// The largest number of steps, a step being the rewrite of a node, or a round, a group of a
+    /// round or the expansion of a cube by mccluskey.
+    pub max_steps: Option<usize>,
+    pub deadline: Option<Instant>,
+    pub cancel: Option<CancelToken>,
+}
+
+/// The simplification stopped before its end.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetExceeded {
+    pub reason: String,
+    /// The smallest equation equivalent to the simplified one found before stopping.
+    pub best: Equation,
+}
+
+impl fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}, best form found : {}", self.reason, self.best.pretty(Style::Arithmetic))
+    }
+}
+
+/// The state of a running simplification.
+struct Context<'a> {
+    trace: &'a mut Trace,
+    budget: &'a Budget,
+    steps: usize,
+    /// Once set every pass returns the nodes unchanged.
+    exceeded: Option<String>,
+}
+
+impl<'a> Context<'a> {
+    /// Counts a step and returns false if the budget is exceeded.
+    fn step(&mut self) -> bool {
+        if self.exceeded.is_some() {
+            return false;
+        }
+        self.steps += 1;
+        if self.budget.max_steps.is_some_and(|max| self.steps > max) {
+            self.exceed(format!("more than {} steps", self.steps - 1));
+        }
+        self.check()
+    }
+
+    /// Returns false if the budget is exceeded, the deadline reached or the simplification
+    /// cancelled, without counting a step.
+    fn check(&mut self) -> bool {
+        if self.exceeded.is_some() {
+            return false;
+        }
+        let budget = self.budget;
+        if budget.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            self.exceed("deadline reached".to_string());
+        } else if budget.cancel.as_ref().is_some_and(|c| c.is_cancelled()) {
+            self.exceed("cancelled".to_string());
+        }
+        self.exceeded.is_none()
+    }
+
+    fn exceed(&mut self, reason: String) {
+        self.exceeded = Some(reason);
+    }
+}
+
+/// The passes of complete_simplify.
+impl Default for SimplifyOptions {
+    fn default() -> Self {
+        SimplifyOptions::new(vec![Pass::Nnf, Pass::Distribution, Pass::Flatten, Pass::Absorption, Pass::Mccluskey])
+    }
+}
+
+/// A literal as its variable and its value.
+type Literal = (String, bool);
+
+fn literal(eq: &Equation) -> Option<Literal> {
+    match *eq {
+        Equation::Var(ref v) => Some((v.clone(), true)),
+        Equation::Not(_) => match *eq.inners()[0] {
+            Equation::Var(ref v) => Some((v.clone(), false)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn literal_eq(lit: &Literal) -> Equation {
+    match lit.1 {
+        true => Equation::var(&lit.0),
+        false => !Equation::var(&lit.0),
+    }
+}
+
+/// Returns the literals of a product of literals if is_sum is false, of a sum of literals
+/// otherwise.
+fn literals(eq: &Equation, is_sum: bool) -> Option<Vec<Literal>> {
+    match *eq {
+        Equation::Sum(_) if !is_sum => None,
+        Equation::Prod(_) if is_sum => None,
+        Equation::Sum(_) | Equation::Prod(_) => eq.inners().into_iter().map(literal).collect(),
+        _ => literal(eq).map(|l| vec![l]),
+    }
+}
+
+/// Returns the cubes of a sum of products of literals.
+fn cubes(eq: &Equation) -> Option<Vec<Vec<Literal>>> {
+    match *eq {
+        Equation::Sum(_) => eq.inners().into_iter().map(|i| literals(i, false)).collect(),
+        _ => None,
+    }
+}
+
+/// Returns a single literal as is, the others as a product.
+fn cube_eq(cube: &[Literal]) -> Equation {
+    match cube.len() {
+        1 => literal_eq(&cube[0]),
+        _ => Equation::all(cube.iter().map(literal_eq)),
+    }
+}
+
+/// True for a product of literals or a sum of them.
+fn is_sop(eq: &Equation) -> bool {
+    literals(eq, false).is_some() || cubes(eq).is_some()
+}
+
+fn nnf(eq: Equation, negated: bool, ctx: &mut Context) -> Equation {
+    if !ctx.step() {
+        return if negated { !eq } else { eq };
+    }
+    let before = match negated {
+        true => ctx.trace.snapshot(|| !eq.clone()),
+        false => None,
+    };
+    match eq {
+        Equation::Var(_) if negated => !eq,
+        Equation::Var(_) => eq,
+        Equation::Not(_) => {
+            let ret = nnf(eq.into_inners().pop().unwrap(), !negated, ctx);
+            ctx.trace.record("double negation", before, &ret);
+            ret
+        }
+        Equation::Sum(_) | Equation::Prod(_) => {
+            let is_sum = matches!(eq, Equation::Sum(_));
+            let inner = eq
+                .into_inners()
+                .into_iter()
+                .map(|i| nnf(i, negated, ctx))
+                .collect::<Vec<Equation>>();
+            let ret = match is_sum != negated {
+                true => Equation::any(inner),
+                false => Equation::all(inner),
+            };
+            ctx.trace.record("de morgan", before, &ret);
+            ret
+        }
+    }
+}
+
+fn flatten(eq: Equation, ctx: &mut Context) -> Equation {
+    eq.rewrite(&mut |eq| {
+        let is_sum = match eq {
+            Equation::Sum(_) => true,
+            Equation::Prod(_) => false,
+            _ => return eq,
+        };
+        if !ctx.step() {
+            return eq;
+        }
+        let before = ctx.trace.snapshot(|| eq.clone());
+        let mut inner = vec![];
+        for i in eq.into_inners() {
+            match i {
+                Equation::Sum(_) if is_sum => inner.append(&mut i.into_inners()),
+                Equation::Prod(_) if !is_sum => inner.append(&mut i.into_inners()),
+                _ => inner.push(i),
+            }
+        }
+        let (ret, rule) = match (inner.len(), is_sum) {
+            (1, _) => (inner.pop().unwrap(), "single operand"),
+            (_, true) => (Equation::any(inner), "flatten sum"),
+            (_, false) => (Equation::all(inner), "flatten product"),
+        };
+        ctx.trace.record(rule, before, &ret);
+        ret
+    })
+}
+
+/// Returns the operands of the equation if it is an operator of the given kind, the nested
+/// operators of that kind being merged, itself otherwise.
+fn operands(eq: Equation, is_sum: bool) -> Vec<Equation> {
+    match eq {
+        Equation::Sum(_) if is_sum => eq.into_inners().into_iter().flat_map(|i| operands(i, true)).collect(),
+        Equation::Prod(_) if !is_sum => eq.into_inners().into_iter().flat_map(|i| operands(i, false)).collect(),
+        _ => vec![eq],
+    }
+}
+
+fn distribution(eq: Equation, ctx: &mut Context) -> Equation {
+    eq.rewrite(&mut |eq| {
+        let has_sum = match eq {
+            Equation::Prod(_) => eq.inners().iter().any(|i| matches!(**i, Equation::Sum(_))),
+            _ => false,
+        };
+        if !has_sum || !ctx.step() {
+            return eq;
+        }
+        let nb_products = eq
+            .inners()
+            .iter()
+            .fold(1usize, |n, i| n.saturating_mul(operands((*i).clone(), true).len()));
+        if let Some(max) = ctx.budget.max_terms.filter(|&max| nb_products > max) {
+            ctx.exceed(format!("a distribution of {} products, more than {}", nb_products, max));
+            return eq;
+        }
+        let before = ctx.trace.snapshot(|| eq.clone());
+        let mut products: Vec<Vec<Equation>> = vec![vec![]];
+        for factor in eq.inners() {
+            let terms = operands(factor.clone(), true);
+            let mut next = Vec::with_capacity(products.len().saturating_mul(terms.len()));
+            for p in products.iter() {
+                // the expansion may be exponential, the deadline is checked for every product
+                if !ctx.check() {
+                    return eq;
+                }
+                for t in terms.iter() {
+                    let mut product = p.clone();
+                    product.append(&mut operands(t.clone(), false));
+                    next.push(product);
+                }
+            }
+            products = next;
+        }
+        let ret = Equation::any(products.into_iter().map(Equation::all));
+        ctx.trace.record("distribution", before, &ret);
+        ret
+    })
+}
+
+/// Removes the duplicated literals, then the operands which are always absorbed : the
+/// contradictions of a sum, the tautologies of a product and the operands containing another one.
+fn absorb(eq: Equation, ctx: &mut Context) -> Equation {
+    eq.rewrite(&mut |eq| {
+        let is_sum = match eq {
+            Equation::Sum(_) => true,
+            Equation::Prod(_) => false,
+            _ => return eq,
+        };
+        if !ctx.step() {
+            return eq;
+        }
+        let before = ctx.trace.snapshot(|| eq.clone());
+        let mut kept: Vec<(Equation, Vec<Literal>)> = vec![];
+        let mut others = vec![];
+        for i in eq.into_inners() {
+            let mut lits = match literals(&i, !is_sum) {
+                Some(lits) => lits,
+                None => {
+                    others.push(i);
+                    continue;
+                }
+            };
+            let nb_lits = lits.len();
+            lits.sort();
+            lits.dedup();
+            if lits.windows(2).any(|w| w[0].0 == w[1].0) {
+                continue;
+            }
+            let i = match lits.len() == nb_lits {
+                true => i,
+                false if is_sum => Equation::all(lits.iter().map(literal_eq)),
+                false => Equation::any(lits.iter().map(literal_eq)),
+            };
+            kept.push((i, lits));
+        }
+        let contains = |a: &Vec<Literal>, b: &Vec<Literal>| b.iter().all(|l| a.contains(l));
+        let mut inner = vec![];
+        for (i, (eq, lits)) in kept.iter().enumerate() {
+            // of two equal operands the first one is kept
+            let absorbed = kept.iter().enumerate().any(|(j, (_, other))| {
+                j != i && contains(lits, other) && (lits.len() != other.len() || j < i)
+            });
+            if !absorbed {
+                inner.push(eq.clone());
+            }
+        }
+        inner.append(&mut others);
+        let ret = match is_sum {
+            true => Equation::any(inner),
+            false => Equation::all(inner),
+        };
+        ctx.trace.record("absorption", before, &ret);
+        ret
+    })
+}
+
+/// Applies the minimization to the sums of cubes whose number of variables is accepted.
+fn minimize<F: FnMut(Equation, &mut Context) -> Equation>(
+    eq: Equation,
+    accept: &dyn Fn(usize) -> bool,
+    rule: &'static str,
+    ctx: &mut Context,
+    f: &mut F,
+) -> Equation {
+    eq.rewrite(&mut |eq| {
+        if cubes(&eq).is_none() || !accept(eq.get_vars().len()) || !ctx.step() {
+            return eq;
+        }
+        let before = ctx.trace.snapshot(|| eq.clone());
+        let ret = f(eq, ctx);
+        ctx.trace.record(rule, before, &ret);
+        ret
+    })
+}
+
+fn mccluskey(eq: Equation, accept: &dyn Fn(usize) -> bool, ctx: &mut Context) -> Equation {
+    minimize(eq, accept, "mccluskey", ctx, &mut |mut eq, ctx| {
+        eq.mccluskey_checked(&mut || ctx.step());
+        eq
+    })
+}
+
+/// Expands every cube by removing the literals it does not need, then removes the cubes covered
+/// by the others, the smallest ones first.
+fn espresso(eq: Equation, accept: &dyn Fn(usize) -> bool, ctx: &mut Context) -> Equation {
+    minimize(eq, accept, "espresso", ctx, &mut |eq, ctx| {
+        let mut cubes = cubes(&eq).unwrap();
+        let mut bdd = Bdd::new(eq.get_owned_vars());
+        let f = bdd.build(&eq);
+        let not_f = bdd.not(f);
+        for cube in cubes.iter_mut() {
+            let mut i = 0;
+            while i < cube.len() && ctx.step() {
+                let mut expanded = cube.clone();
+                expanded.remove(i);
+                let node = bdd.build(&cube_eq(&expanded));
+                match bdd.and(node, not_f) == bdd::FALSE {
+                    true => *cube = expanded,
+                    false => i += 1,
+                }
+            }
+        }
+        cubes.sort_by_key(|c| (c.len(), c.clone()));
+        cubes.dedup();
+        for i in (0..cubes.len()).rev() {
+            if !ctx.step() {
+                break;
+            }
+            let others = cubes
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .map(|(_, c)| cube_eq(c))
+                .collect::<Vec<Equation>>();
+            let rest = bdd.build(&Equation::any(others));
+            if rest == f {
+                cubes.remove(i);
+            }
+        }
+        Equation::any(cubes.iter().map(|c| cube_eq(c)))
+    })
+}
+
+/// Factors out the literal of most cubes until no literal is shared, eg.
+/// a * b + a * c + d => a * (b + c) + d
+fn factor(cubes: Vec<Vec<Literal>>, ctx: &mut Context) -> Equation {
+    let mut counts = BTreeMap::new();
+    for cube in cubes.iter() {
+        for lit in cube.iter() {
+            *counts.entry(lit.clone()).or_insert(0) += 1;
+        }
+    }
+    let mut best: Option<(Literal, usize)> = None;
+    for (lit, count) in counts {
+        if count > best.as_ref().map_or(1, |b| b.1) {
+            best = Some((lit, count));
+        }
+    }
+    let lit = match best {
+        Some((ref lit, _)) if ctx.step() => lit.clone(),
+        _ => return Equation::any(cubes.iter().map(|c| cube_eq(c))),
+    };
+    let (with, without): (Vec<Vec<Literal>>, Vec<Vec<Literal>>) =
+        cubes.into_iter().partition(|c| c.contains(&lit));
+    let quotients = with
+        .into_iter()
+        .map(|c| c.into_iter().filter(|l| *l != lit).collect::<Vec<Literal>>())
+        .collect::<Vec<Vec<Literal>>>();
+    // a + a * b = a
+    let factored = match quotients.iter().any(|q| q.is_empty()) {
+        true => literal_eq(&lit),
+        false => Equation::all(vec![literal_eq(&lit), factor(quotients, ctx)]),
+    };
+    match without.is_empty() {
+        true => factored,
+        false => {
+            let mut terms = vec![factored];
+            terms.append(&mut operands(factor(without, ctx), true));
+            Equation::any(terms)
+        }
+    }
+}
+
+fn factoring(eq: Equation, ctx: &mut Context) -> Equation {
+    minimize(eq, &|_| true, "factoring", ctx, &mut |eq, ctx| factor(cubes(&eq).unwrap(), ctx))
+}
+
+impl Pass {
+    fn run(self, eq: Equation, options: &SimplifyOptions, ctx: &mut Context) -> Equation {
+        match self {
+            Pass::Nnf => nnf(eq, false, ctx),
+            Pass::Flatten => flatten(eq, ctx),
+            Pass::Absorption => absorb(eq, ctx),
+            Pass::Distribution => distribution(eq, ctx),
+            Pass::Mccluskey => match options.max_exact_vars {
+                Some(max) => {
+                    let eq = mccluskey(eq, &|n| n <= max, ctx);
+                    espresso(eq, &|n| n > max, ctx)
+                }
+                None => mccluskey(eq, &|_| true, ctx),
+            },
+            Pass::Espresso => espresso(eq, &|_| true, ctx),
+            Pass::Factoring => factoring(eq, ctx),
+        }
+    }
+}
+
+impl Equation {
+    /// Runs the passes of the options and returns the equation in the chosen form, or the best
+    /// form found if the budget is exceeded.
+    pub fn simplify_with(self, options: &SimplifyOptions) -> Equation {
+        self.simplify_with_traced(options, &mut Trace::disabled())
+    }
+
+    /// Same as simplify_with, recording every rewrite in the trace.
+    pub fn simplify_with_traced(self, options: &SimplifyOptions, trace: &mut Trace) -> Equation {
+        self.try_simplify_with_traced(options, trace).unwrap_or_else(|e| e.best)
+    }
+
+    /// Same as simplify_with, returning an error if the budget is exceeded.
+    pub fn try_simplify_with(self, options: &SimplifyOptions) -> Result<Equation, BudgetExceeded> {
+        self.try_simplify_with_traced(options, &mut Trace::disabled())
+    }
+
+    /// Same as try_simplify_with, recording every rewrite in the trace.
+    pub fn try_simplify_with_traced(
+        self,
+        options: &SimplifyOptions,
+        trace: &mut Trace,
+    ) -> Result<Equation, BudgetExceeded> {
+        let mut ctx = Context {
+            trace,
+            budget: &options.budget,
+            steps: 0,
+            exceeded: None,
+        };
+        // a product of sums is the negation of the sum of products of the negation
+        let negated = options.form == Form::Pos;
+        let eq = match negated {
+            true => !self,
+            false => self,
+        };
+        let mut best = eq.clone();
+        let eq = run_passes(eq, options, &mut ctx, &mut best);
+        let eq = match options.form {
+            Form::MultiLevel => eq,
+            _ => to_sop(eq, options, &mut ctx),
+        };
+        if let Some(reason) = ctx.exceeded {
+            keep_smaller(&mut best, &eq);
+            let best = match negated {
+                true => !best,
+                false => best,
+            };
+            return Err(BudgetExceeded { reason, best });
+        }
+        Ok(match negated {
+            true => flatten(nnf(!eq, false, &mut ctx), &mut ctx),
+            false => eq,
+        })
+    }
+}
+
+/// Returns the number of nodes of the tree.
+fn size(eq: &Equation) -> usize {
+    let mut nodes = 0;
+    eq.walk(&mut |_| nodes += 1);
+    nodes
+}
+
+fn keep_smaller(best: &mut Equation, eq: &Equation) {
+    if size(eq) < size(best) {
+        *best = eq.clone();
+    }
+}
+
+/// Runs the passes until a fixpoint or the budget is exceeded, keeping the smallest equation
+/// found between the passes.
+fn run_passes(mut eq: Equation, options: &SimplifyOptions, ctx: &mut Context, best: &mut Equation) -> Equation {
+    for _ in 0..options.max_rounds {
+        let old = eq.clone();
+        for pass in options.passes.iter() {
+            eq = pass.run(eq, options, ctx);
+            keep_smaller(best, &eq);
+            if ctx.exceeded.is_some() {
+                return eq;
+            }
+        }
+        if eq == old {
+            break;
+        }
+    }
+    eq
+}
+
+/// Expands the equation if the passes did not leave a sum of products, then minimizes it.
+fn to_sop(eq: Equation, options: &SimplifyOptions, ctx: &mut Context) -> Equation {
+    if is_sop(&eq) || ctx.exceeded.is_some() {
+        return eq;
+    }
+    let eq = flatten(distribution(flatten(nnf(eq, false, ctx), ctx), ctx), ctx);
+    Pass::Mccluskey.run(absorb(eq, ctx), options, ctx)
+}
+
+#[cfg(test)]
+mod tests_passes {
+    use super::*;
+    use printer::Style;
+
+    fn run(text: &str, passes: Vec<Pass>) -> String {
+        let options = SimplifyOptions {
+            form: Form::MultiLevel,
+            ..SimplifyOptions::new(passes)
+        };
+        let eq = Equation::from(text.to_string());
+        let simplified = eq.clone().simplify_with(&options);
+        assert!(simplified.is_equivalent(&eq));
+        simplified.pretty(Style::Arithmetic)
+    }
+
+    #[test]
+    fn test_single_passes() {
+        assert_eq!(run("!(a * !(b + c))", vec![Pass::Nnf]), "!a + b + c");
+        assert_eq!(run("(a + (b + c)) * (d)", vec![Pass::Flatten]), "(a + b + c) * d");
+        assert_eq!(run("a * (b + c)", vec![Pass::Distribution, Pass::Flatten]), "a * b + a * c");
+        assert_eq!(run("a * b + a + c * !c + b * b", vec![Pass::Absorption]), "a + b");
+        assert_eq!(run("a * b + a * !b + c", vec![Pass::Mccluskey]), "a + c");
+        assert_eq!(run("a*b*c + a*b*!c + a*!b*c", vec![Pass::Mccluskey]), "a * b + a * c");
+        assert_eq!(run("a * b + a * !b + c", vec![Pass::Espresso]), "a + c");
+        assert_eq!(run("a * b + a * c + d", vec![Pass::Factoring]), "a * (b + c) + d");
+    }
+
+    #[test]
+    fn test_forms() {
+        let eq = Equation::from("(a + b) * (a + c) * !(d * e)".to_string());
+        let sop = eq.clone().simplify_with(&SimplifyOptions::default());
+        assert!(is_sop(&sop) && sop.is_equivalent(&eq));
+        let pos = SimplifyOptions {
+            form: Form::Pos,
+            ..SimplifyOptions::default()
+        };
+        let pos = eq.clone().simplify_with(&pos);
+        assert!(pos.inners().iter().all(|c| literals(c, true).is_some()));
+        assert!(pos.is_equivalent(&eq));
+        assert_eq!(format!("{}", pos), "((a + b) * (a + c) * (! d + ! e))");
+        // without the distribution the sum of products is still ensured
+        let nnf = eq.clone().simplify_with(&SimplifyOptions::new(vec![Pass::Nnf]));
+        assert!(is_sop(&nnf) && nnf.is_equivalent(&eq));
+    }
+
+    #[test]
+    fn test_nested_distribution() {
+        let eq = Equation::from("!(a+!b * !(x*z + !(!a*!f + !b*!c + !d*!e)))".to_string());
+        let simplified = eq.clone().complete_simplify();
+        assert!(simplified.is_equivalent(&eq));
+        let mut cubes = cubes(&simplified).unwrap();
+        for cube in cubes.iter_mut() {
+            assert!(!cube.iter().any(|(v, b)| cube.contains(&(v.clone(), !b))), "{:?}", cube);
+            cube.sort();
+        }
+        let len = cubes.len();
+        cubes.sort();
+        cubes.dedup();
+        assert_eq!(cubes.len(), len);
+        let eq = Equation::from("a*(b+c*(d+e)) + a*c*d".to_string());
+        assert_eq!(eq.clone().complete_simplify().pretty(Style::Arithmetic), "a * b + a * c * d + a * c * e");
+        let pos = SimplifyOptions { form: Form::Pos, ..SimplifyOptions::default() };
+        assert_eq!(eq.simplify_with(&pos).pretty(Style::Arithmetic), "a * (b + c) * (b + d + e)");
+    }
+
+    #[test]
+    fn test_limits() {
+        let eq = Equation::from("a*b*c + a*b*!c + a*!b*c + a*!b*!c + d".to_string());
+        let mut options = SimplifyOptions::new(vec![Pass::Mccluskey]);
+        options.max_exact_vars = Some(2);
+        let simplified = eq.clone().simplify_with(&options);
+        assert!(simplified.is_equivalent(&eq));
+        assert_eq!(format!("{}", simplified), "(a + d)");
+    }
+
+    #[test]
+    fn test_rounds() {
+        // the duplicated cube is only created by the distribution, after the absorption
+        let passes = vec![Pass::Absorption, Pass::Distribution, Pass::Flatten];
+        let eq = Equation::from("a * (b + c) + a * b".to_string());
+        let mut options = SimplifyOptions::new(passes);
+        options.form = Form::MultiLevel;
+        let rounds = |max_rounds| {
+            let options = SimplifyOptions { max_rounds, ..options.clone() };
+            eq.clone().simplify_with(&options)
+        };
+        assert_eq!(rounds(0), eq);
+        assert_eq!(rounds(1).pretty(Style::Arithmetic), "a * b + a * b + a * c");
+        assert_eq!(rounds(2).pretty(Style::Arithmetic), "a * b + a * c");
+        assert_eq!(rounds(10), rounds(2));
+    }
+}
+
+#[cfg(test)]
+mod tests_budget {
+    use super::*;
+    use std::thread;
+    use std::time::Duration;
+
+    fn large() -> Equation {
+        Equation::from("(a + b) * (c + d) * (e + f) * (g + h) * (i + j) * (k + l)".to_string())
+    }
+
+    fn with_budget(budget: Budget) -> SimplifyOptions {
+        SimplifyOptions {
+            budget,
+            ..SimplifyOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_max_terms() {
+        let options = with_budget(Budget {
+            max_terms: Some(10),
+            ..Budget::default()
+        });
+        let err = large().try_simplify_with(&options).unwrap_err();
+        assert_eq!(err.reason, "a distribution of 64 products, more than 10");
+        assert!(err.best.is_equivalent(&large()));
+        assert_eq!(large().simplify_with(&options), err.best);
+        let small = Equation::from("(a + b) * c".to_string());
+        assert!(small.try_simplify_with(&options).is_ok());
+    }
+
+    #[test]
+    fn test_max_steps() {
+        let eq = Equation::from("!(a * !(b + c)) * (d + !e) + a * b * !c".to_string());
+        for steps in 0..40 {
+            let options = with_budget(Budget {
+                max_steps: Some(steps),
+                ..Budget::default()
+            });
+            match eq.clone().try_simplify_with(&options) {
+                Ok(simplified) => assert_eq!(simplified, eq.clone().complete_simplify()),
+                Err(err) => {
+                    assert_eq!(err.reason, format!("more than {} steps", steps));
+                    assert!(err.best.is_equivalent(&eq));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_cancel_and_deadline() {
+        let token = CancelToken::new();
+        let options = with_budget(Budget {
+            cancel: Some(token.clone()),
+            ..Budget::default()
+        });
+        assert!(large().try_simplify_with(&options).is_ok());
+        token.cancel();
+        let err = large().try_simplify_with(&options).unwrap_err();
+        assert_eq!(err.reason, "cancelled");
+        assert_eq!(err.best, large());
+        let options = with_budget(Budget {
+            deadline: Some(Instant::now()),
+            ..Budget::default()
+        });
+        assert_eq!(large().try_simplify_with(&options).unwrap_err().reason, "deadline reached");
+        let options = with_budget(Budget {
+            deadline: Some(Instant::now() + Duration::from_secs(60)),
+            ..Budget::default()
+        });
+        assert!(large().try_simplify_with(&options).is_ok());
+    }
+
+    #[test]
+    fn test_exponential_distribution() {
+        // 2^20 products once distributed
+        let factors = (0..20).map(|i| format!("(a{} + b{})", i, i)).collect::<Vec<String>>();
+        let eq = Equation::from(factors.join(" * "));
+        let start = Instant::now();
+        let options = with_budget(Budget {
+            deadline: Some(start + Duration::from_millis(50)),
+            ..Budget::default()
+        });
+        let err = eq.clone().try_simplify_with(&options).unwrap_err();
+        assert_eq!(err.reason, "deadline reached");
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(err.best, eq);
+        let token = CancelToken::new();
+        let canceller = token.clone();
+        let start = Instant::now();
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            canceller.cancel();
+        });
+        let options = with_budget(Budget {
+            cancel: Some(token),
+            ..Budget::default()
+        });
+        let err = eq.clone().try_simplify_with(&options).unwrap_err();
+        handle.join().unwrap();
+        assert_eq!(err.reason, "cancelled");
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_pos_best() {
+        let eq = Equation::from("a * b + c".to_string());
+        let mut options = with_budget(Budget {
+            max_steps: Some(3),
+            ..Budget::default()
+        });
+        options.form = Form::Pos;
+        let err = eq.clone().try_simplify_with(&options).unwrap_err();
+        assert!(err.best.is_equivalent(&eq));
+        assert!(format!("{}", err).starts_with("more than 3 steps, best form found : "));
+    }
+}
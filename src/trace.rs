@@ -0,0 +1,162 @@
+//! Records the rewrites done by the simplification, to explain how a result was found.
+
+use equation::Equation;
+use printer::Style;
+
+/// A rewrite of the simplification.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    pub rule: &'static str,
+    pub before: Equation,
+    pub after: Equation,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trace {
+    steps: Vec<Step>,
+    enabled: bool,
+}
+
+/// Escapes a string for a JSON document.
+fn json_string(text: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+/// A trace recording every rewrite.
+impl Default for Trace {
+    fn default() -> Self {
+        Trace::new()
+    }
+}
+
+impl Trace {
+    pub fn new() -> Self {
+        Trace {
+            steps: vec![],
+            enabled: true,
+        }
+    }
+
+    /// A trace recording nothing, used by the simplifications without trace.
+    pub fn disabled() -> Self {
+        Trace {
+            steps: vec![],
+            enabled: false,
+        }
+    }
+
+    pub fn steps(&self) -> &Vec<Step> {
+        &self.steps
+    }
+
+    /// Returns a copy of the equation before a rewrite, None if nothing is recorded.
+    pub fn snapshot<F: FnOnce() -> Equation>(&self, eq: F) -> Option<Equation> {
+        match self.enabled {
+            true => Some(eq()),
+            false => None,
+        }
+    }
+
+    /// Records the rewrite if the snapshot was taken and the equation changed.
+    pub fn record(&mut self, rule: &'static str, before: Option<Equation>, after: &Equation) {
+        match before {
+            Some(ref before) if before == after => (),
+            Some(before) => self.steps.push(Step {
+                rule,
+                before,
+                after: after.clone(),
+            }),
+            None => (),
+        }
+    }
+
+    /// Returns one line per rewrite, eg. 1. de morgan : !(a + b) => !a * !b
+    pub fn to_text(&self) -> String {
+        self.steps
+            .iter()
+            .enumerate()
+            .map(|(i, s)| {
+                format!(
+                    "{}. {} : {} => {}\n",
+                    i + 1,
+                    s.rule,
+                    s.before.pretty(Style::Arithmetic),
+                    s.after.pretty(Style::Arithmetic)
+                )
+            })
+            .collect()
+    }
+
+    /// Returns a JSON array of objects with the rule, before and after fields.
+    pub fn to_json(&self) -> String {
+        let steps = self
+            .steps
+            .iter()
+            .map(|s| {
+                format!(
+                    "{{\"rule\":{},\"before\":{},\"after\":{}}}",
+                    json_string(s.rule),
+                    json_string(&s.before.pretty(Style::Arithmetic)),
+                    json_string(&s.after.pretty(Style::Arithmetic))
+                )
+            })
+            .collect::<Vec<String>>();
+        format!("[{}]", steps.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests_trace {
+    use super::*;
+
+    fn rules(trace: &Trace) -> Vec<&'static str> {
+        trace.steps().iter().map(|s| s.rule).collect()
+    }
+
+    #[test]
+    fn test_rules() {
+        let mut trace = Trace::new();
+        let eq = Equation::from("!(a + !!b)".to_string());
+        let simplified = eq.clone().complete_simplify_traced(&mut trace);
+        assert_eq!(simplified, eq.complete_simplify());
+        assert!(rules(&trace).contains(&"de morgan"));
+        assert!(rules(&trace).contains(&"double negation"));
+        let mut trace = Trace::new();
+        Equation::from("a * (b + c)".to_string()).complete_simplify_traced(&mut trace);
+        let step = trace.steps().iter().find(|s| s.rule == "distribution").unwrap();
+        assert_eq!(step.before.pretty(Style::Arithmetic), "a * (b + c)");
+        assert_eq!(step.after.pretty(Style::Arithmetic), "a * b + a * c");
+        for step in trace.steps() {
+            step.before.compare_random_values(&step.after, 100);
+        }
+    }
+
+    #[test]
+    fn test_rendering() {
+        let mut trace = Trace::new();
+        let before = Equation::from("!(a * b)".to_string());
+        trace.record("de morgan", Some(before.clone()), &Equation::from("!a + !b".to_string()));
+        trace.record("nothing", Some(before.clone()), &before);
+        assert_eq!(trace.to_text(), "1. de morgan : !(a * b) => !a + !b\n");
+        assert_eq!(
+            trace.to_json(),
+            "[{\"rule\":\"de morgan\",\"before\":\"!(a * b)\",\"after\":\"!a + !b\"}]"
+        );
+        let mut disabled = Trace::disabled();
+        let snapshot = disabled.snapshot(|| before.clone());
+        disabled.record("de morgan", snapshot, &Equation::var("a"));
+        assert!(disabled.steps().is_empty());
+        assert_eq!(json_string("a\"b\\"), "\"a\\\"b\\\\\"");
+    }
+}